@@ -1,7 +1,33 @@
-use rq_core::parser::{HttpFile, HttpRequest};
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use arboard::Clipboard;
+use rq_core::{
+    assertions::{self, Summary},
+    compression::CompressionReport,
+    diff::{binary_diff, DiffChunk},
+    encoding::{encode_with_encoding, LineEnding},
+    environment::Environments,
+    exchange::{Exchange, ExchangeLog},
+    export::snippet::snippet,
+    format::{indent_json, DEFAULT_INDENT},
+    mime::{parse_multipart, Part},
+    parser::{HttpFile, HttpRequest},
+    progress::{format_bytes, Progress},
+    request::{format_elapsed, Response, Session},
+    sse::{is_event_stream, parse_events},
+    variables::{capture_headers, extract, resolve, Resolution, Variables},
+};
+use tokio::sync::{
+    mpsc::{channel, Receiver, Sender},
+    watch,
+};
 
-use std::error::Error;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    error::Error,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 use crossterm::event::{
     Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
@@ -9,36 +35,423 @@ use crossterm::event::{
 
 use tui::widgets::ListState;
 
+use crate::{
+    browser,
+    command::parse_command,
+    config::{Config, SPLIT_RATIO_RANGE},
+    diagnose::Diagnosis,
+    favorites,
+    snippets::{self, Snippet},
+    symbols::{self, Symbols},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Requests,
+    Response,
+}
+
+/// Vim-style modes, see [`Config::vim_mode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimMode {
+    Normal,
+    /// Selection of response lines
+    Visual,
+    /// Typing in the command line
+    Insert,
+}
+
+impl VimMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VimMode::Normal => "NORMAL",
+            VimMode::Visual => "VISUAL",
+            VimMode::Insert => "INSERT",
+        }
+    }
+}
+
+/// Auto-scroll of the response to the bottom, like `tail -f`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Follow {
+    #[default]
+    Off,
+    /// Pinned to the bottom as the content changes
+    On,
+    /// Suspended by scrolling up, resumed when scrolling back to the bottom
+    Paused,
+}
+
+impl Follow {
+    pub fn toggle(self) -> Self {
+        match self {
+            Follow::Off => Follow::On,
+            Follow::On | Follow::Paused => Follow::Off,
+        }
+    }
+
+    /// State after a manual scroll to `scroll`, where `bottom` is the offset showing the last line
+    pub fn scrolled(self, scroll: u16, bottom: u16) -> Self {
+        match self {
+            Follow::On if scroll < bottom => Follow::Paused,
+            Follow::Paused if scroll >= bottom => Follow::On,
+            state => state,
+        }
+    }
+
+    /// Scroll offset to draw the response with
+    pub fn scroll(self, scroll: u16, bottom: u16) -> u16 {
+        if self == Follow::On {
+            bottom
+        } else {
+            scroll
+        }
+    }
+}
+
+/// Responses of a request sent once per media type of its `# @accept-matrix`
+#[derive(Debug, Default)]
+pub struct AcceptVariants {
+    media_types: Vec<String>,
+    /// Variants not sent yet
+    queued: VecDeque<HttpRequest>,
+    /// Variants received so far, in the order of `media_types`
+    responses: Vec<(HttpRequest, Result<Response, String>)>,
+    /// Index of the variant shown in the response pane
+    shown: usize,
+}
+
+impl AcceptVariants {
+    /// Splits the variants into the first request to send and the state tracking the others
+    fn new(variants: Vec<(String, HttpRequest)>) -> Option<(HttpRequest, Self)> {
+        let (media_types, mut queued): (Vec<_>, VecDeque<_>) = variants.into_iter().unzip();
+        let first = queued.pop_front()?;
+        Some((
+            first,
+            Self {
+                media_types,
+                queued,
+                ..Default::default()
+            },
+        ))
+    }
+
+    /// Moves the shown variant by `delta`, wrapping around the received ones
+    fn cycle(&mut self, delta: isize) {
+        let len = self.responses.len() as isize;
+        if len > 0 {
+            self.shown = (self.shown as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    /// Media type of the shown variant and its position, e.g. `Accept: text/html (2/3)`
+    pub fn label(&self) -> String {
+        format!(
+            "Accept: {} ({}/{})",
+            self.media_types[self.shown],
+            self.shown + 1,
+            self.media_types.len()
+        )
+    }
+}
+
+/// Input popups
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Prompt {
+    /// Header name to jump to
+    Header,
+    /// Path to save the response body to
+    SavePath,
+    /// Encoding of the saved response body
+    SaveEncoding { path: String },
+    /// Name of the variable to extract a response value to
+    ExtractName,
+    /// Header name or JSON path of the extracted value
+    ExtractExpression { name: String },
+    /// Ad-hoc request, e.g. `get https://example.com`
+    Command,
+    /// Substring of the names or values of the response headers to show, applied as typed
+    HeaderFilter,
+    /// Text to find in the response
+    ResponseSearch,
+}
+
+impl Prompt {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Prompt::Header => "Jump to header",
+            Prompt::SavePath => "Save response to",
+            Prompt::SaveEncoding { .. } => "Encoding (raw for the received bytes)",
+            Prompt::ExtractName => "Variable name",
+            Prompt::ExtractExpression { .. } => "Header or $.json.path",
+            Prompt::Command => ":",
+            Prompt::HeaderFilter => "Filter headers",
+            Prompt::ResponseSearch => "Search the response",
+        }
+    }
+}
+
 pub struct App {
-    res_rx: Receiver<String>,
+    res_rx: Receiver<(HttpRequest, Result<Response, String>)>,
     req_tx: Sender<HttpRequest>,
+    /// Body bytes received for the pending request and its `Content-Length`
+    progress_rx: watch::Receiver<(u64, Option<u64>)>,
 
     pub requests: Vec<HttpRequest>,
+    pub response: Option<Response>,
+    /// Request that produced the current response
+    pub response_request: Option<HttpRequest>,
+    /// Latest responses and the url of their request, newest last
+    history: Vec<(String, Response)>,
+    /// Diff of the last two binary responses of the same url, shown in a popup
+    pub binary_diff: Option<Vec<DiffChunk>>,
     pub response_buffer: String,
+    pub response_scroll: u16,
+    pub follow: Follow,
+    /// Percentage of the screen used by the requests list
+    pub list_percent: u16,
+    /// Config written back when settings change, and its path
+    config: (Config, Option<PathBuf>),
+    /// Whether the settings changed since the config was last written
+    config_dirty: bool,
+    /// Lines of the response pane, updated on every draw
+    pub response_height: u16,
+    /// Index of the request waiting for a response and when it was sent.
+    /// The index is `None` for ad-hoc requests of the command line
+    pending: Option<(Option<usize>, Instant)>,
+    /// Overrides the automatic wrap trimming of the response, see [`Config`]
+    pub trim_response_whitespace: Option<bool>,
     pub list: ListState,
-    pub cursor_position: (u16, u16),
+    /// Active search query, `None` when not searching
+    pub search: Option<String>,
+    /// Whether the search bar is capturing key events
+    pub search_editing: bool,
+    /// Indices of the requests matching the search query
+    pub match_indices: Vec<usize>,
+    /// Characters of the [`request_line`] matched by the search query, by request index
+    pub match_positions: HashMap<usize, Vec<usize>>,
+    pub snippets: Vec<Snippet>,
+    /// Open input popup and its current value
+    pub prompt: Option<(Prompt, String)>,
+    /// Last searched header name, used to pre-populate the header prompt
+    last_header: String,
+    /// Only the response headers matching this filter are shown, see [`filter_headers`]
+    pub header_filter: Option<String>,
+    /// Text searched in the response, highlighted when drawn
+    pub response_query: Option<String>,
+    /// Response lines matching [`App::response_query`]
+    pub response_matches: Vec<u16>,
+    /// Response line the search prompt was opened at, restored when it is cancelled
+    response_search_origin: u16,
+    /// Feedback shown in the status bar until the next key press
+    pub message: Option<String>,
+    /// Selection of the snippet picker, `None` when closed
+    pub snippet_picker: Option<ListState>,
+    /// Requests of the global favorites file, available in every session
+    pub favorites: Vec<HttpRequest>,
+    /// Path of the favorites file, see [`Config::favorites_path`]
+    pub favorites_path: Option<PathBuf>,
+    /// Selection of the favorites picker, `None` when closed
+    pub favorite_picker: Option<ListState>,
+    /// Last mouse click, consumed on the next draw to update `focus`
+    pub click_position: Option<(u16, u16)>,
+    pub focus: Focus,
+    /// Whether the exit confirmation popup is open
+    pub confirm_exit: bool,
     pub exited: bool,
     pub file_path: String,
+    pub environments: Environments,
+    /// Name of the active environment
+    pub environment: Option<String>,
+    /// Values captured from responses, substituted in the next requests
+    pub variables: Variables,
+    /// Environment variables and the `.env` file next to the requests file, substituted
+    /// after the captured variables
+    pub env_variables: Variables,
+    /// Whether the variables popup is open
+    pub variables_open: bool,
+    /// Resolution of the placeholders of the selected request, `None` when the popup is closed
+    pub variable_trace: Option<Vec<Resolution>>,
+    /// Text that couldn't be copied, shown in a popup to copy it by hand
+    pub copy_fallback: Option<String>,
+    /// Assertion result of the executed requests by index, see [`assertions::check`]
+    assertion_results: BTreeMap<usize, Option<bool>>,
+    /// Indices of the requests whose last send failed without a response
+    failed_requests: BTreeSet<usize>,
+    /// Index of the request whose body should be opened in the editor
+    pub edit_request: Option<usize>,
+    pub symbols: &'static Symbols,
+    /// See [`Config::use_nerd_font_icons`]
+    pub nerd_font_icons: bool,
+    /// See [`Config::pretty_request_body`]
+    pub pretty_request_body: bool,
+    /// See [`Config::save_line_endings`]
+    save_line_endings: LineEnding,
+    /// See [`Config::rich_render_max_bytes`]
+    rich_render_max_bytes: usize,
+    /// Whether the current response is formatted regardless of its size
+    force_rich: bool,
+    /// See [`Config::tint_response_body`]
+    pub tint_response_body: bool,
+    /// Whether responses are shown as received, without formatting
+    show_raw: bool,
+    /// Whether the response buffer holds a formatted JSON body, highlighted when drawn
+    pub json_body: bool,
+    /// See [`Config::redact`]
+    redact: Vec<String>,
+    /// Path and exchanges of the session log, see `--log`
+    pub exchange_log: Option<(String, ExchangeLog)>,
+    pub stats: SessionStats,
+    /// Whether the statistics popup is open
+    pub stats_open: bool,
+    /// Repeated runs of a request shown in a popup, `None` when closed
+    pub diagnosis: Option<Diagnosis>,
+    /// Used to send requests outside of the request worker
+    session: Session,
+    /// Whether the key bindings popup is open
+    pub help_open: bool,
+    /// Responses of a replayed log by request index, see `--replay`.
+    /// Requests are not sent when set
+    pub replay: Option<Vec<Response>>,
+    /// Variants of the last request with an accept matrix, `None` for other requests
+    pub accept_variants: Option<AcceptVariants>,
+    /// Current mode, `None` when [`Config::vim_mode`] is disabled
+    pub vim_mode: Option<VimMode>,
+    /// Response line where the visual selection started
+    visual_start: u16,
+    /// Opened on the first copy. Kept alive since on X11 the copied text
+    /// is only available while the clipboard is open
+    clipboard: Option<Clipboard>,
+}
+
+/// Metrics of the requests sent during the session
+#[derive(Debug, Default)]
+pub struct SessionStats {
+    pub total: usize,
+    /// Responses with a 2xx status
+    pub success: usize,
+    /// Requests that failed without a response
+    pub error: usize,
+    /// Body bytes received, before decompression
+    pub total_bytes: u64,
+    /// Response times measured by the client. Requests without a response aren't timed
+    pub timings: Vec<Duration>,
+    pub slowest: Option<(String, Duration)>,
+    pub fastest: Option<(String, Duration)>,
+}
+
+impl SessionStats {
+    fn record(&mut self, url: &str, res: Result<&Response, &String>) {
+        self.total += 1;
+        let response = match res {
+            Ok(response) => response,
+            Err(_) => {
+                self.error += 1;
+                return;
+            }
+        };
+        if response.status.is_success() {
+            self.success += 1;
+        }
+        let received = response
+            .compression
+            .as_ref()
+            .map_or(response.bytes.len(), |c| c.compressed);
+        self.total_bytes += received as u64;
+
+        // The time the UI noticed the response depends on the tick rate
+        let elapsed = response.elapsed;
+        self.timings.push(elapsed);
+        if self.slowest.as_ref().is_none_or(|(_, d)| elapsed > *d) {
+            self.slowest = Some((url.to_string(), elapsed));
+        }
+        if self.fastest.as_ref().is_none_or(|(_, d)| elapsed < *d) {
+            self.fastest = Some((url.to_string(), elapsed));
+        }
+    }
+
+    /// Percentage of 2xx responses
+    pub fn success_rate(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.success as f64 * 100.0 / self.total as f64
+    }
+
+    pub fn average(&self) -> Option<Duration> {
+        let total: Duration = self.timings.iter().sum();
+        (!self.timings.is_empty()).then(|| total / self.timings.len() as u32)
+    }
+
+    pub fn median(&self) -> Option<Duration> {
+        let mut timings = self.timings.clone();
+        timings.sort();
+        let mid = timings.len() / 2;
+        match timings.len() {
+            0 => None,
+            n if n % 2 == 0 => Some((timings[mid - 1] + timings[mid]) / 2),
+            _ => Some(timings[mid]),
+        }
+    }
 }
 
-fn handle_requests(mut req_rx: Receiver<HttpRequest>, res_tx: Sender<String>) {
-    tokio::spawn(async move {
+/// Number of responses kept for the binary diff
+const HISTORY_SIZE: usize = 20;
+
+#[derive(Debug, PartialEq, Eq)]
+enum ExitDecision {
+    Exit,
+    Confirm,
+}
+
+/// Exiting with a pending request or bodies edited with `e` asks for confirmation,
+/// unless `Ctrl-c` is pressed again while the confirmation is open
+fn exit_decision(pending: bool, edited: bool, confirm_open: bool, ctrl_c: bool) -> ExitDecision {
+    if !(pending || edited) || (confirm_open && ctrl_c) {
+        ExitDecision::Exit
+    } else {
+        ExitDecision::Confirm
+    }
+}
+
+fn handle_requests(
+    mut req_rx: Receiver<HttpRequest>,
+    res_tx: Sender<(HttpRequest, Result<Response, String>)>,
+    progress_tx: watch::Sender<(u64, Option<u64>)>,
+    session: Session,
+) {
+    rq_core::runtime::spawn(async move {
         while let Some(req) = req_rx.recv().await {
-            let data = match rq_core::request::execute(&req).await {
-                Ok(r) => r,
-                Err(e) => e.to_string(),
-            };
-            res_tx.send(data).await.unwrap();
+            let data = session
+                .execute_with_progress(&req, |received, total| {
+                    progress_tx.send_replace((received, total));
+                })
+                .await
+                .map_err(|e| e.to_string());
+            // Reset before the response is sent so the next request starts from zero
+            progress_tx.send_replace((0, None));
+            // The app is gone, nothing is left to send requests
+            if res_tx.send((req, data)).await.is_err() {
+                break;
+            }
         }
     });
 }
 
 impl App {
-    pub fn new(file_path: String, http_file: HttpFile) -> Self {
+    pub fn new(
+        file_path: String,
+        http_file: HttpFile,
+        config: &Config,
+        session: Session,
+        snippets: Vec<Snippet>,
+    ) -> Self {
         let (req_tx, req_rx) = channel::<HttpRequest>(1);
-        let (res_tx, res_rx) = channel::<String>(1);
+        let (res_tx, res_rx) = channel::<(HttpRequest, Result<Response, String>)>(1);
+
+        let (progress_tx, progress_rx) = watch::channel((0, None));
 
-        handle_requests(req_rx, res_tx);
+        handle_requests(req_rx, res_tx, progress_tx, session.clone());
 
         let mut list = ListState::default();
         list.select(Some(0));
@@ -46,16 +459,205 @@ impl App {
             file_path,
             res_rx,
             req_tx,
+            progress_rx,
             list,
             requests: http_file.requests,
+            response: None,
+            response_request: None,
+            history: Vec::new(),
+            binary_diff: None,
             response_buffer: String::new(),
-            cursor_position: (0, 0),
+            response_scroll: 0,
+            follow: Follow::Off,
+            list_percent: config.split_ratio(),
+            config: (config.clone(), Config::path()),
+            config_dirty: false,
+            response_height: 0,
+            pending: None,
+            trim_response_whitespace: config.trim_response_whitespace,
+            search: None,
+            search_editing: false,
+            match_indices: Vec::new(),
+            match_positions: HashMap::new(),
+            snippets,
+            snippet_picker: None,
+            favorites: Vec::new(),
+            favorites_path: None,
+            favorite_picker: None,
+            prompt: None,
+            last_header: String::new(),
+            header_filter: None,
+            response_query: None,
+            response_matches: Vec::new(),
+            response_search_origin: 0,
+            message: None,
+            click_position: None,
+            focus: Focus::Requests,
+            confirm_exit: false,
             exited: false,
+            environments: Environments::new(),
+            environment: None,
+            variables: Variables::new(),
+            env_variables: Variables::new(),
+            variables_open: false,
+            variable_trace: None,
+            copy_fallback: None,
+            assertion_results: BTreeMap::new(),
+            failed_requests: BTreeSet::new(),
+            edit_request: None,
+            symbols: &symbols::UNICODE,
+            nerd_font_icons: config.use_nerd_font_icons,
+            pretty_request_body: config.pretty_request_body,
+            save_line_endings: config.save_line_endings,
+            rich_render_max_bytes: config.rich_render_max_bytes,
+            tint_response_body: config.tint_response_body,
+            redact: config.redact.clone(),
+            force_rich: false,
+            show_raw: false,
+            json_body: false,
+            exchange_log: None,
+            replay: None,
+            stats: SessionStats::default(),
+            stats_open: false,
+            diagnosis: None,
+            session,
+            help_open: false,
+            accept_variants: None,
+            vim_mode: config.vim_mode.then_some(VimMode::Normal),
+            visual_start: 0,
+            clipboard: None,
+        }
+    }
+
+    /// Number of requests whose body was edited with `e`, lost on exit
+    pub fn edited_bodies(&self) -> usize {
+        self.requests
+            .iter()
+            .filter(|req| req.body_override.is_some())
+            .count()
+    }
+
+    fn request_exit(&mut self, ctrl_c: bool) {
+        let edited = self.edited_bodies() > 0;
+        match exit_decision(self.pending.is_some(), edited, self.confirm_exit, ctrl_c) {
+            ExitDecision::Exit => self.exited = true,
+            ExitDecision::Confirm => self.confirm_exit = true,
         }
     }
 
+    /// Selected request with its edited body, see [`App::resolve`]
     fn selected_request(&self) -> HttpRequest {
-        self.requests[self.list.selected().unwrap()].clone()
+        let mut req = self.requests[self.list.selected().unwrap()].clone();
+        if let Some(body) = req.body_override.take() {
+            req.body = body;
+        }
+        self.resolve(req)
+    }
+
+    /// Applies the defaults of the active environment, the captured variables and then
+    /// the environment variables
+    fn resolve(&self, req: HttpRequest) -> HttpRequest {
+        let req = match self
+            .environment
+            .as_ref()
+            .and_then(|name| self.environments.get(name))
+        {
+            Some(env) => env.resolve(&req),
+            None => req,
+        };
+        req.with_variables(&self.variables)
+            .with_variables(&self.env_variables)
+    }
+
+    /// What the placeholders of the selected request resolve to, after applying the environment
+    fn trace_variables(&self) -> Vec<Resolution> {
+        let mut req = self.requests[self.list.selected().unwrap()].clone();
+        if let Some(body) = req.body_override.take() {
+            req.body = body;
+        }
+        let req = match self
+            .environment
+            .as_ref()
+            .and_then(|name| self.environments.get(name))
+        {
+            Some(env) => env.resolve(&req),
+            None => req,
+        };
+        req.trace_variables(&self.variables, &self.env_variables, self.base_dir())
+    }
+
+    /// Directory of the requests file, body helper files are relative to it
+    fn base_dir(&self) -> &Path {
+        Path::new(&self.file_path).parent().unwrap_or(Path::new(""))
+    }
+
+    /// Checks that every variable of the resolved request is defined, that its `< path` body
+    /// exists and evaluates the body helpers, with files relative to the requests file.
+    /// Errors are shown in the status bar and the request isn't sent
+    fn prepare_request(&mut self, req: HttpRequest) -> Option<HttpRequest> {
+        let req = match resolve(&req, &self.env_variables) {
+            Ok(req) => req,
+            Err(names) => {
+                self.message = Some(format!("Undefined variables: {}", names.join(", ")));
+                return None;
+            }
+        };
+        if let Some(path) = &req.body_file {
+            let path = self.base_dir().join(path);
+            if !path.is_file() {
+                self.message = Some(format!("Body file not found: {}", path.display()));
+                return None;
+            }
+        }
+        match req.with_helpers(self.base_dir()) {
+            Ok(req) => Some(req),
+            Err(e) => {
+                self.message = Some(format!("Error evaluating the body: {}", e));
+                None
+            }
+        }
+    }
+
+    /// Variables the selected request references but no environment or capture defines
+    pub fn unresolved_variables(&self) -> Vec<String> {
+        if self.requests.is_empty() {
+            return Vec::new();
+        }
+        self.selected_request().unresolved_variables()
+    }
+
+    /// Body to open in the editor, the override if it was already edited
+    pub fn request_body(&self, index: usize) -> String {
+        let req = &self.requests[index];
+        req.body_override
+            .clone()
+            .unwrap_or_else(|| req.body.clone())
+    }
+
+    pub fn set_body_override(&mut self, index: usize, edited: std::io::Result<String>) {
+        match edited {
+            Ok(body) => self.requests[index].body_override = Some(body.trim().to_string()),
+            Err(e) => self.message = Some(format!("Editor failed: {}", e)),
+        }
+    }
+
+    /// Tally of the assertions of the executed requests
+    pub fn assertion_summary(&self) -> Summary {
+        Summary::from_results(&self.assertion_results.values().copied().collect::<Vec<_>>())
+    }
+
+    /// Cycles through the environments, ending with no environment
+    fn next_environment(&mut self) {
+        let mut names = self.environments.keys();
+        self.environment = match &self.environment {
+            Some(current) => names.skip_while(|name| *name != current).nth(1),
+            None => names.next(),
+        }
+        .cloned();
+        self.message = Some(format!(
+            "Environment: {}",
+            self.environment.as_deref().unwrap_or("none")
+        ));
     }
 
     fn next(&mut self) {
@@ -77,9 +679,651 @@ impl App {
         self.list.select(Some(i));
     }
 
+    /// Whether the last send of the request at `index` failed without a response
+    pub fn request_failed(&self, index: usize) -> bool {
+        self.failed_requests.contains(&index)
+    }
+
+    /// Whether a request is waiting for its response
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// When the request at `index` was sent if it's waiting for a response
+    pub fn pending_since(&self, index: usize) -> Option<Instant> {
+        self.pending
+            .filter(|(i, _)| *i == Some(index))
+            .map(|(_, since)| since)
+    }
+
+    /// First and last response lines of the visual selection
+    pub fn visual_selection(&self) -> Option<(u16, u16)> {
+        (self.vim_mode == Some(VimMode::Visual)).then(|| {
+            (
+                self.visual_start.min(self.response_scroll),
+                self.visual_start.max(self.response_scroll),
+            )
+        })
+    }
+
+    /// Number of lines of the current response
+    pub fn response_lines(&self) -> usize {
+        self.response_buffer.lines().count()
+    }
+
+    /// Scroll offset showing the last response line at the bottom of the pane
+    pub fn response_bottom(&self) -> u16 {
+        let lines = u16::try_from(self.response_lines()).unwrap_or(u16::MAX);
+        lines.saturating_sub(self.response_height)
+    }
+
+    /// Pauses or resumes following after a manual scroll
+    fn update_follow(&mut self) {
+        self.follow = self
+            .follow
+            .scrolled(self.response_scroll, self.response_bottom());
+    }
+
+    fn scroll_down(&mut self) {
+        if (self.response_scroll as usize) + 1 < self.response_lines() {
+            self.response_scroll += 1;
+        }
+    }
+
+    /// Diffs the last two binary responses for the url of the current response
+    fn diff_binary_responses(&mut self) {
+        let url = match &self.response_request {
+            Some(req) => &req.url,
+            None => return,
+        };
+        let mut responses = self
+            .history
+            .iter()
+            .rev()
+            .filter(|(u, res)| u == url && res.binary)
+            .map(|(_, res)| res);
+        match (responses.next(), responses.next()) {
+            (Some(new), Some(old)) => {
+                self.binary_diff = Some(binary_diff(&old.bytes, &new.bytes));
+            }
+            _ => self.message = Some("Binary diff needs two binary responses for this url".into()),
+        }
+    }
+
+    /// Whether leading whitespace of the response is trimmed when wrapping lines
+    pub fn trim_response(&self) -> bool {
+        let structured = self
+            .response
+            .as_ref()
+            .and_then(|r| r.content_type())
+            .is_some_and(is_structured);
+        self.trim_response_whitespace.unwrap_or(!structured)
+    }
+
+    /// Line of the response where the header `name` is defined
+    pub fn find_header_line(&self, name: &str) -> Option<u16> {
+        find_header_line(&self.response_buffer, name)
+    }
+
+    fn jump_to_section(&mut self, section: Section) {
+        if self.response.is_none() {
+            return;
+        }
+        let offsets = section_offsets(&self.response_buffer);
+        self.response_scroll = match section {
+            Section::Headers => offsets.headers,
+            Section::Body => offsets.body,
+        };
+        self.update_follow();
+    }
+
+    /// Scrolls to the next or previous part of a multipart response
+    fn jump_to_part(&mut self, forward: bool) {
+        let offsets = part_offsets(&self.response_buffer);
+        let scroll = self.response_scroll;
+        let target = if forward {
+            offsets.into_iter().find(|&line| line > scroll)
+        } else {
+            offsets.into_iter().rev().find(|&line| line < scroll)
+        };
+        if let Some(line) = target {
+            self.response_scroll = line;
+            self.update_follow();
+        }
+    }
+
+    fn jump_to_header(&mut self, name: &str) {
+        if let Some(line) = self.find_header_line(name) {
+            self.response_scroll = line;
+            self.update_follow();
+        }
+    }
+
+    fn on_prompt_key_event(&mut self, event: KeyEvent) {
+        let (prompt, value) = match self.prompt.as_mut() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        let filtering = *prompt == Prompt::HeaderFilter;
+        let searching = *prompt == Prompt::ResponseSearch;
+        match event.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Backspace => {
+                value.pop();
+            }
+            KeyCode::Char(c) => value.push(c),
+            KeyCode::Enter => {
+                if let Some((prompt, value)) = self.prompt.take() {
+                    self.submit_prompt(prompt, value);
+                }
+            }
+            _ => {}
+        }
+
+        match &self.prompt {
+            Some((Prompt::HeaderFilter, value)) => {
+                self.set_header_filter(Some(value.clone()).filter(|v| !v.is_empty()))
+            }
+            Some((Prompt::ResponseSearch, value)) => {
+                let query = value.clone();
+                self.preview_response_search(query);
+            }
+            // Esc clears the filter, Enter keeps it
+            None if filtering && event.code == KeyCode::Esc => self.set_header_filter(None),
+            None if searching && event.code == KeyCode::Esc => {
+                self.preview_response_search(String::new());
+            }
+            _ => {}
+        }
+    }
+
+    fn set_header_filter(&mut self, filter: Option<String>) {
+        if filter == self.header_filter {
+            return;
+        }
+        self.header_filter = filter;
+        if self.response.is_some() {
+            self.refresh_response();
+            self.response_scroll = 0;
+        }
+    }
+
+    /// Rendering of the current response body, see [`render_mode`]
+    pub fn render_mode(&self) -> RenderMode {
+        let len = self.response.as_ref().map_or(0, |r| r.body.len());
+        render_mode(len, self.rich_render_max_bytes, self.force_rich)
+    }
+
+    /// Formats the current response into the response buffer
+    fn refresh_response(&mut self) {
+        self.json_body = false;
+        if let Some(response) = &self.response {
+            let rich = self.render_mode() == RenderMode::Rich && !self.show_raw;
+            if rich && is_json(response) && !response.binary {
+                self.json_body = indent_json(&response.body, DEFAULT_INDENT).is_ok();
+                if !self.json_body {
+                    self.message = Some("Invalid JSON body, shown as received".to_string());
+                }
+            }
+            self.response_buffer = format_response(response, self.header_filter.as_deref(), rich);
+        }
+        self.update_response_matches();
+    }
+
+    fn update_response_matches(&mut self) {
+        self.response_matches = match self.response_query.as_deref() {
+            Some(query) => (0..)
+                .zip(self.response_buffer.lines())
+                .filter(|(_, line)| !match_ranges(line, query).is_empty())
+                .map(|(i, _)| i)
+                .collect(),
+            None => Vec::new(),
+        };
+    }
+
+    /// Highlights `query` in the response while it is typed and scrolls to its first match
+    /// from the line the search started at
+    fn preview_response_search(&mut self, query: String) {
+        self.response_query = Some(query).filter(|q| !q.is_empty());
+        self.update_response_matches();
+        self.response_scroll = self.response_search_origin;
+        if !self.response_matches.is_empty() {
+            self.response_scroll = self.response_scroll.saturating_sub(1);
+            self.jump_to_response_match(true);
+        } else {
+            self.update_follow();
+        }
+    }
+
+    /// Searches `query` in the response, keeping the highlights when it matches
+    fn search_response(&mut self, query: String) {
+        self.preview_response_search(query);
+        if self.response_matches.is_empty() {
+            if let Some(query) = self.response_query.take() {
+                self.message = Some(format!("No matches for {}", query));
+            }
+        }
+    }
+
+    /// Scrolls to the next or previous line matching the response search, wrapping around
+    fn jump_to_response_match(&mut self, forward: bool) {
+        let scroll = self.response_scroll;
+        let target = if forward {
+            let next = self.response_matches.iter().find(|&&line| line > scroll);
+            next.or_else(|| self.response_matches.first())
+        } else {
+            let previous = self
+                .response_matches
+                .iter()
+                .rev()
+                .find(|&&line| line < scroll);
+            previous.or_else(|| self.response_matches.last())
+        };
+        if let Some(&line) = target {
+            let position = self.response_matches.iter().position(|&l| l == line);
+            self.message = Some(format!(
+                "Match {} of {}",
+                position.unwrap_or_default() + 1,
+                self.response_matches.len()
+            ));
+            self.response_scroll = line;
+            self.update_follow();
+        }
+    }
+
+    fn submit_prompt(&mut self, prompt: Prompt, value: String) {
+        match prompt {
+            Prompt::Header => {
+                self.jump_to_header(&value);
+                self.last_header = value;
+            }
+            Prompt::SavePath => {
+                self.prompt = Some((Prompt::SaveEncoding { path: value }, "utf-8".to_string()));
+            }
+            Prompt::ExtractName if !value.is_empty() => {
+                self.prompt = Some((Prompt::ExtractExpression { name: value }, String::new()));
+            }
+            Prompt::ExtractName | Prompt::HeaderFilter => {}
+            Prompt::ResponseSearch => self.search_response(value),
+            Prompt::ExtractExpression { name } => {
+                let extracted = self
+                    .response
+                    .as_ref()
+                    .and_then(|response| extract(response, &value));
+                self.message = Some(match extracted {
+                    Some(extracted) => {
+                        let message = format!("{{{{{}}}}} = {}", name, extracted);
+                        self.variables.insert(name, extracted);
+                        message
+                    }
+                    None => format!("Nothing found for {}", value),
+                });
+            }
+            Prompt::Command => match parse_command(&value) {
+                Ok(req) => self.send_adhoc(req),
+                Err(e) => self.message = Some(e),
+            },
+            Prompt::SaveEncoding { path } => {
+                self.message = Some(match self.save_response(&path, &value) {
+                    Ok(message) => message,
+                    Err(e) => format!("Error: {}", e),
+                });
+            }
+        }
+    }
+
+    fn save_response(&self, path: &str, encoding: &str) -> Result<String, Box<dyn Error>> {
+        match &self.response {
+            Some(response) => save_body(response, path, encoding, self.save_line_endings),
+            None => Err("no response to save".into()),
+        }
+    }
+
+    fn update_matches(&mut self) {
+        self.match_indices.clear();
+        self.match_positions.clear();
+        let query = match self.search.as_deref() {
+            Some(query) if !query.is_empty() => query.to_lowercase(),
+            _ => return,
+        };
+        for (i, req) in self.requests.iter().enumerate() {
+            // Fuzzy on the request line, substring on the rest of the fields
+            if let Some((_, positions)) = fuzzy_match(&request_line(req), &query) {
+                self.match_indices.push(i);
+                self.match_positions.insert(i, positions);
+            } else if request_matches(req, &query) {
+                self.match_indices.push(i);
+            }
+        }
+    }
+
+    fn next_match(&mut self) {
+        let selected = self.list.selected().unwrap();
+        let next = self
+            .match_indices
+            .iter()
+            .find(|&&i| i > selected)
+            .or_else(|| self.match_indices.first());
+        if let Some(&i) = next {
+            self.list.select(Some(i));
+        }
+    }
+
+    fn previous_match(&mut self) {
+        let selected = self.list.selected().unwrap();
+        let previous = self
+            .match_indices
+            .iter()
+            .rev()
+            .find(|&&i| i < selected)
+            .or_else(|| self.match_indices.last());
+        if let Some(&i) = previous {
+            self.list.select(Some(i));
+        }
+    }
+
+    fn clear_search(&mut self) {
+        self.search = None;
+        self.search_editing = false;
+        self.match_indices.clear();
+        self.match_positions.clear();
+    }
+
+    fn on_search_key_event(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Esc => {
+                self.clear_search();
+                self.set_header_filter(None);
+            }
+            KeyCode::Enter => {
+                self.search_editing = false;
+                self.next_match();
+            }
+            KeyCode::Backspace => {
+                self.search.get_or_insert_with(String::new).pop();
+                self.update_matches();
+            }
+            KeyCode::Char(c) => {
+                self.search.get_or_insert_with(String::new).push(c);
+                self.update_matches();
+            }
+            _ => {}
+        }
+    }
+
+    /// Sends a request that is not in the file, like the ones of the command line or the favorites
+    fn send_adhoc(&mut self, req: HttpRequest) {
+        let Some(req) = self.prepare_request(self.resolve(req)) else {
+            return;
+        };
+        if self.send(req, None) {
+            self.accept_variants = None;
+        }
+    }
+
+    /// Sends `req`, sent from the request at `index` if any, unless a request is pending.
+    /// Responses are matched to the pending request, so only one is sent at a time
+    fn send(&mut self, req: HttpRequest, index: Option<usize>) -> bool {
+        if self.pending.is_some() || self.req_tx.try_send(req).is_err() {
+            self.message = Some("A request is already pending".to_string());
+            return false;
+        }
+        self.response_buffer = String::from("Loading...");
+        self.response_scroll = 0;
+        self.pending = Some((index, Instant::now()));
+        true
+    }
+
+    /// Appends the selected request to the favorites file, with its variables unresolved
+    fn add_favorite(&mut self) {
+        let Some(path) = self.favorites_path.clone() else {
+            self.message = Some("No config directory for the favorites file".to_string());
+            return;
+        };
+        let mut req = self.requests[self.list.selected().unwrap()].clone();
+        if let Some(body) = req.body_override.take() {
+            req.body = body;
+        }
+        self.message = Some(match favorites::append_favorite(&path, &req) {
+            Ok(()) => {
+                let message = format!("Added {} to the favorites", request_line(&req));
+                self.favorites.push(req);
+                message
+            }
+            Err(e) => format!("Error writing {}: {}", path.display(), e),
+        });
+    }
+
+    fn on_favorite_key_event(&mut self, event: KeyEvent) {
+        let picker = match self.favorite_picker.as_mut() {
+            Some(picker) => picker,
+            None => return,
+        };
+        let selected = picker.selected().unwrap_or_default();
+        match event.code {
+            KeyCode::Esc => self.favorite_picker = None,
+            KeyCode::Down => picker.select(Some((selected + 1) % self.favorites.len())),
+            KeyCode::Up => picker.select(Some(
+                selected.checked_sub(1).unwrap_or(self.favorites.len() - 1),
+            )),
+            KeyCode::Enter => {
+                self.favorite_picker = None;
+                self.send_adhoc(self.favorites[selected].clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn on_snippet_key_event(&mut self, event: KeyEvent) {
+        let picker = match self.snippet_picker.as_mut() {
+            Some(picker) => picker,
+            None => return,
+        };
+        let selected = picker.selected().unwrap_or_default();
+        match event.code {
+            KeyCode::Esc => self.snippet_picker = None,
+            KeyCode::Down => picker.select(Some((selected + 1) % self.snippets.len())),
+            KeyCode::Up => picker.select(Some(
+                selected.checked_sub(1).unwrap_or(self.snippets.len() - 1),
+            )),
+            KeyCode::Enter => {
+                let index = self.list.selected().unwrap_or_default();
+                let at =
+                    snippets::insert_snippet(&mut self.requests, index, &self.snippets[selected]);
+                self.list.select(Some(at));
+                self.snippet_picker = None;
+                // Indices after the snippet are shifted
+                self.assertion_results.clear();
+                self.failed_requests.clear();
+            }
+            _ => {}
+        }
+    }
+
     pub fn tick(&mut self) {
-        if let Ok(res) = self.res_rx.try_recv() {
-            self.response_buffer = res;
+        if let Some(diagnosis) = self.diagnosis.as_mut() {
+            diagnosis.poll();
+        }
+        if let Some(progress) = self.download_progress() {
+            self.response_buffer = format!("Loading... {}", progress);
+        } else if let Some((_, sent)) = self.pending.filter(|_| self.accept_variants.is_none()) {
+            // Waiting for the headers. The variants already received stay shown
+            self.response_buffer = format!("Loading... {}", format_elapsed(sent.elapsed()));
+        }
+        if let Ok((req, res)) = self.res_rx.try_recv() {
+            let pending = self.pending.take();
+            if let Some((index, _)) = pending {
+                if let Some(index) = index {
+                    let result = match &res {
+                        Ok(response) => assertions::check(&req, response),
+                        Err(_) => req.expect_status.map(|_| false),
+                    };
+                    self.assertion_results.insert(index, result);
+                    if res.is_err() {
+                        self.failed_requests.insert(index);
+                    } else {
+                        self.failed_requests.remove(&index);
+                    }
+                }
+                self.stats.record(&req.url, res.as_ref());
+                if let Ok(response) = &res {
+                    self.log_exchange(&req, response, response.elapsed);
+                }
+            }
+            if let Some(variants) = self.accept_variants.as_mut() {
+                variants.responses.push((req.clone(), res.clone()));
+                variants.shown = variants.responses.len() - 1;
+                // The worker is idle once it sent the response, so the channel has room
+                if let Some(next) = variants.queued.pop_front() {
+                    self.pending = pending.map(|(index, _)| (index, Instant::now()));
+                    let _ = self.req_tx.try_send(next);
+                }
+                self.message = Some(variants.label());
+            }
+            self.show_response(req, res);
+        }
+    }
+
+    /// Shows the next or previous variant of a request with an accept matrix
+    fn cycle_accept_variant(&mut self, delta: isize) {
+        let Some(variants) = self.accept_variants.as_mut() else {
+            return;
+        };
+        variants.cycle(delta);
+        let (req, res) = &variants.responses[variants.shown];
+        self.message = Some(variants.label());
+        self.response_request = Some(req.clone());
+        match res {
+            Ok(response) => self.response = Some(response.clone()),
+            Err(e) => {
+                self.response_buffer = e.clone();
+                self.response = None;
+            }
+        }
+        self.refresh_response();
+        self.response_scroll = 0;
+    }
+
+    /// Progress of the pending request once its body started arriving
+    fn download_progress(&self) -> Option<Progress> {
+        let (_, sent) = self.pending?;
+        let (received, total) = *self.progress_rx.borrow();
+        (received > 0).then(|| Progress {
+            received,
+            total,
+            elapsed: sent.elapsed(),
+        })
+    }
+
+    /// Moves the split between the panes by `delta` percent
+    fn resize_panes(&mut self, delta: i16) {
+        let (min, max) = SPLIT_RATIO_RANGE;
+        let percent = self
+            .list_percent
+            .saturating_add_signed(delta)
+            .clamp(min, max);
+        if percent != self.list_percent {
+            self.list_percent = percent;
+            self.config_dirty = true;
+        }
+    }
+
+    /// Writes the changed settings to the config file. Called once per tick
+    /// so that repeated changes result in a single write
+    pub fn save_config(&mut self) {
+        if !std::mem::take(&mut self.config_dirty) {
+            return;
+        }
+        let (config, path) = &mut self.config;
+        config.panel_split_ratio = self.list_percent;
+        if let Some(path) = path {
+            if let Err(e) = config.save(path) {
+                self.message = Some(format!("Error saving the config: {}", e));
+            }
+        }
+    }
+
+    fn show_response(&mut self, req: HttpRequest, res: Result<Response, String>) {
+        self.response_request = Some(req.clone());
+        self.response_query = None;
+        self.response_matches.clear();
+        match res {
+            Ok(response) => {
+                if req.has_header("accept-encoding") {
+                    self.message =
+                        Some(CompressionReport(response.compression.as_ref()).to_string());
+                }
+                let missing = capture_headers(&req, &response, &mut self.variables);
+                if let Some(header) = missing.first() {
+                    self.message = Some(format!("Header {} not found for capture", header));
+                }
+                if self.history.len() == HISTORY_SIZE {
+                    self.history.remove(0);
+                }
+                self.history.push((req.url.clone(), response.clone()));
+                self.response = Some(response);
+                self.force_rich = false;
+                self.refresh_response();
+                if self.render_mode() == RenderMode::Plain {
+                    let size = self.response.as_ref().map_or(0, |r| r.body.len());
+                    self.message = Some(format!(
+                        "Large response ({}) shown as received, press R to format it",
+                        format_bytes(size as u64)
+                    ));
+                }
+            }
+            Err(e) => {
+                self.response_buffer = e;
+                self.response = None;
+            }
+        }
+        self.response_scroll = 0;
+        if self.vim_mode == Some(VimMode::Visual) {
+            self.vim_mode = Some(VimMode::Normal);
+        }
+    }
+
+    /// Copies `text` to the system clipboard, reporting the outcome in the status bar.
+    /// Without a clipboard, e.g. over SSH, the text is shown in a popup instead
+    fn copy_to_clipboard(&mut self, text: String, what: &str) {
+        let copied = match self.clipboard.as_mut() {
+            Some(clipboard) => clipboard.set_text(text.clone()),
+            None => Clipboard::new()
+                .and_then(|clipboard| self.clipboard.insert(clipboard).set_text(text.clone())),
+        };
+        self.message = Some(match copied {
+            Ok(()) => format!("Copied {} to the clipboard", what),
+            Err(e) => {
+                self.copy_fallback = Some(text);
+                format!("Clipboard unavailable: {}", e)
+            }
+        });
+    }
+
+    /// Appends the exchange to the log file, if enabled with `--log`
+    fn log_exchange(&mut self, req: &HttpRequest, response: &Response, elapsed: Duration) {
+        let Some((path, log)) = self.exchange_log.as_mut() else {
+            return;
+        };
+        log.exchanges.push(Exchange::new(req, response, elapsed));
+        if let Err(e) = write_log(path, log) {
+            self.message = Some(format!("Failed to write {}: {}", path, e));
+        }
+    }
+
+    /// Shows the logged response of the selected request without sending it
+    fn replay_response(&mut self) {
+        let index = self.list.selected().unwrap();
+        match self
+            .replay
+            .as_ref()
+            .and_then(|responses| responses.get(index))
+        {
+            Some(response) => {
+                let res = Ok(response.clone());
+                self.show_response(self.requests[index].clone(), res);
+            }
+            None => self.message = Some("Request not in the replayed log".to_string()),
         }
     }
 
@@ -97,30 +1341,1154 @@ impl App {
     fn on_mouse_event(&mut self, ev: MouseEvent) {
         match ev.kind {
             MouseEventKind::Up(MouseButton::Left) => {
-                self.cursor_position = (ev.column, ev.row);
+                self.click_position = Some((ev.column, ev.row));
             }
             _ => {}
         }
     }
 
     async fn on_key_event(&mut self, event: KeyEvent) -> Result<(), Box<dyn Error>> {
+        if event.code == KeyCode::Char('c') && event.modifiers == KeyModifiers::CONTROL {
+            self.request_exit(true);
+            return Ok(());
+        }
+
+        if self.confirm_exit {
+            match event.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => self.exited = true,
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => self.confirm_exit = false,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if self.search_editing {
+            self.on_search_key_event(event);
+            return Ok(());
+        }
+
+        self.message = None;
+
+        if self.prompt.is_some() {
+            self.on_prompt_key_event(event);
+            if self.prompt.is_none() && self.vim_mode == Some(VimMode::Insert) {
+                self.vim_mode = Some(VimMode::Normal);
+            }
+            return Ok(());
+        }
+
+        if self.snippet_picker.is_some() {
+            self.on_snippet_key_event(event);
+            return Ok(());
+        }
+
+        if self.favorite_picker.is_some() {
+            self.on_favorite_key_event(event);
+            return Ok(());
+        }
+
+        if self.variables_open {
+            if matches!(event.code, KeyCode::Esc | KeyCode::Char('V')) {
+                self.variables_open = false;
+            }
+            return Ok(());
+        }
+
+        if self.variable_trace.is_some() {
+            if matches!(event.code, KeyCode::Esc | KeyCode::Char('T')) {
+                self.variable_trace = None;
+            }
+            return Ok(());
+        }
+
+        if self.copy_fallback.is_some() {
+            if matches!(event.code, KeyCode::Esc | KeyCode::Enter) {
+                self.copy_fallback = None;
+            }
+            return Ok(());
+        }
+
+        if self.stats_open || self.help_open {
+            if matches!(event.code, KeyCode::Esc | KeyCode::Char('S' | '?')) {
+                self.stats_open = false;
+                self.help_open = false;
+            }
+            return Ok(());
+        }
+
+        if self.diagnosis.is_some() {
+            // Dropping the diagnosis stops the remaining runs
+            if matches!(event.code, KeyCode::Esc | KeyCode::Char('D')) {
+                self.diagnosis = None;
+            }
+            return Ok(());
+        }
+
+        if self.binary_diff.is_some() {
+            if matches!(event.code, KeyCode::Esc | KeyCode::Char('B')) {
+                self.binary_diff = None;
+            }
+            return Ok(());
+        }
+
+        if let Some(mode) = self.vim_mode {
+            match (mode, event.code) {
+                (VimMode::Normal, KeyCode::Char('i')) => {
+                    self.vim_mode = Some(VimMode::Insert);
+                    self.prompt = Some((Prompt::Command, String::new()));
+                    return Ok(());
+                }
+                (VimMode::Normal, KeyCode::Char('v'))
+                    if self.focus == Focus::Response && self.response.is_some() =>
+                {
+                    self.vim_mode = Some(VimMode::Visual);
+                    self.visual_start = self.response_scroll;
+                    return Ok(());
+                }
+                (VimMode::Visual, KeyCode::Esc) => {
+                    self.vim_mode = Some(VimMode::Normal);
+                    return Ok(());
+                }
+                // The selection belongs to the response pane
+                (VimMode::Visual, KeyCode::Tab) => self.vim_mode = Some(VimMode::Normal),
+                _ => {}
+            }
+        }
+
         match event.code {
-            KeyCode::Char('q') | KeyCode::Char('Q') => {
-                self.exited = true;
+            KeyCode::Char('q') | KeyCode::Char('Q') => self.request_exit(false),
+            KeyCode::Char('f') if event.modifiers == KeyModifiers::CONTROL => {
+                self.search = Some(String::new());
+                self.search_editing = true;
+                self.match_indices.clear();
+            }
+            KeyCode::Char('t')
+                if event.modifiers == KeyModifiers::CONTROL && !self.snippets.is_empty() =>
+            {
+                let mut picker = ListState::default();
+                picker.select(Some(0));
+                self.snippet_picker = Some(picker);
+            }
+            KeyCode::Char('g') if event.modifiers == KeyModifiers::CONTROL => {
+                if self.favorites.is_empty() {
+                    self.message = Some("No favorites, press * on a request to add it".to_string());
+                } else {
+                    let mut picker = ListState::default();
+                    picker.select(Some(0));
+                    self.favorite_picker = Some(picker);
+                }
             }
-            KeyCode::Char('c') => {
-                if event.modifiers == KeyModifiers::CONTROL {
-                    self.exited = true;
+            KeyCode::Char('*') if self.focus == Focus::Requests && self.replay.is_none() => {
+                self.add_favorite()
+            }
+            KeyCode::Char('e')
+                if event.modifiers == KeyModifiers::CONTROL && !self.environments.is_empty() =>
+            {
+                self.next_environment()
+            }
+            KeyCode::Char('e') if self.focus == Focus::Requests => {
+                self.edit_request = self.list.selected();
+            }
+            KeyCode::Char('E') if self.focus == Focus::Requests => {
+                let index = self.list.selected().unwrap();
+                if self.requests[index].body_override.take().is_some() {
+                    self.message = Some("Restored original body".to_string());
+                }
+            }
+            KeyCode::Char('o') if self.focus == Focus::Requests => {
+                let url = self.selected_request().full_url();
+                self.message = Some(match browser::open(&url) {
+                    Ok(()) => format!("Opened {}", url),
+                    Err(e) => format!("Error opening the browser: {}", e),
+                });
+            }
+            KeyCode::Char('D') if self.focus == Focus::Requests && self.replay.is_none() => {
+                let req = self.selected_request();
+                self.diagnosis = Some(Diagnosis::start(req, self.session.clone()));
+            }
+            KeyCode::Char('c' | 'y') if self.focus == Focus::Requests => {
+                let req = self.selected_request();
+                self.copy_to_clipboard(req.to_curl(), "curl command");
+            }
+            KeyCode::Char('Y') if self.focus == Focus::Requests => {
+                let req = self.selected_request();
+                self.copy_to_clipboard(req.to_httpie(), "HTTPie command");
+            }
+            KeyCode::Char('C') if self.focus == Focus::Response => {
+                if let (Some(req), Some(response)) = (&self.response_request, &self.response) {
+                    let text = snippet(req, response, &self.redact);
+                    self.copy_to_clipboard(text, "the request and response");
+                }
+            }
+            KeyCode::Char('n')
+                if self.focus == Focus::Response && self.response_query.is_some() =>
+            {
+                self.jump_to_response_match(true)
+            }
+            KeyCode::Char('N')
+                if self.focus == Focus::Response && self.response_query.is_some() =>
+            {
+                self.jump_to_response_match(false)
+            }
+            KeyCode::Char('n') if self.search.is_some() => self.next_match(),
+            KeyCode::Char('N') if self.search.is_some() => self.previous_match(),
+            KeyCode::Esc => {
+                self.clear_search();
+                self.set_header_filter(None);
+                self.response_query = None;
+                self.response_matches.clear();
+            }
+            KeyCode::Char('/') if self.focus == Focus::Response && self.response.is_some() => {
+                let query = self.response_query.clone().unwrap_or_default();
+                self.prompt = Some((Prompt::ResponseSearch, query));
+                self.response_search_origin = self.response_scroll;
+            }
+            KeyCode::Char('h') if self.focus == Focus::Response && self.response.is_some() => {
+                let filter = self.header_filter.clone().unwrap_or_default();
+                self.prompt = Some((Prompt::HeaderFilter, filter));
+            }
+            KeyCode::Char('R') if self.render_mode() == RenderMode::Plain => {
+                self.force_rich = true;
+                self.refresh_response();
+            }
+            KeyCode::Char('r') if self.focus == Focus::Response && self.response.is_some() => {
+                self.show_raw = !self.show_raw;
+                self.refresh_response();
+                self.message = Some(if self.show_raw {
+                    "Showing responses as received".to_string()
+                } else {
+                    "Showing formatted responses".to_string()
+                });
+            }
+            KeyCode::Char('H') if self.focus == Focus::Response => {
+                self.prompt = Some((Prompt::Header, self.last_header.clone()));
+            }
+            KeyCode::Char('s') if self.focus == Focus::Response && self.response.is_some() => {
+                self.prompt = Some((Prompt::SavePath, String::new()));
+            }
+            KeyCode::Char('x') if self.focus == Focus::Response && self.response.is_some() => {
+                self.prompt = Some((Prompt::ExtractName, String::new()));
+            }
+            KeyCode::Char('F') => {
+                self.follow = self.follow.toggle();
+                self.message = Some(match self.follow {
+                    Follow::Off => "Stopped following the response".to_string(),
+                    _ => "Following the response".to_string(),
+                });
+            }
+            KeyCode::Char('<') => self.resize_panes(-5),
+            KeyCode::Char('>') => self.resize_panes(5),
+            KeyCode::Char('V') => self.variables_open = true,
+            KeyCode::Char('T') if !self.requests.is_empty() => {
+                self.variable_trace = Some(self.trace_variables())
+            }
+            KeyCode::Char('S') => self.stats_open = true,
+            KeyCode::Char('?') => self.help_open = true,
+            KeyCode::Char(':') => self.prompt = Some((Prompt::Command, String::new())),
+            KeyCode::Char('B') if self.focus == Focus::Response => self.diff_binary_responses(),
+            KeyCode::Char('{') if self.focus == Focus::Response => {
+                self.jump_to_section(Section::Headers)
+            }
+            KeyCode::Char('}') if self.focus == Focus::Response => {
+                self.jump_to_section(Section::Body)
+            }
+            KeyCode::Char('p') if self.focus == Focus::Response => self.jump_to_part(true),
+            KeyCode::Char('P') if self.focus == Focus::Response => self.jump_to_part(false),
+            // The empty line separates headers from the body
+            KeyCode::Char('b') if self.focus == Focus::Response => self.jump_to_header(""),
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Requests => Focus::Response,
+                    Focus::Response => Focus::Requests,
+                }
+            }
+            KeyCode::Down => match self.focus {
+                Focus::Requests => self.next(),
+                Focus::Response => {
+                    self.scroll_down();
+                    self.update_follow();
+                }
+            },
+            KeyCode::Up => match self.focus {
+                Focus::Requests => self.previous(),
+                Focus::Response => {
+                    self.response_scroll = self.response_scroll.saturating_sub(1);
+                    self.update_follow();
                 }
+            },
+            KeyCode::Enter if self.replay.is_some() => self.replay_response(),
+            KeyCode::Char(c @ ('[' | ']'))
+                if self.focus == Focus::Response && self.accept_variants.is_some() =>
+            {
+                self.cycle_accept_variant(if c == ']' { 1 } else { -1 })
             }
-            KeyCode::Down => self.next(),
-            KeyCode::Up => self.previous(),
             KeyCode::Enter => {
-                self.response_buffer = String::from("Loading...");
-                self.req_tx.send(self.selected_request()).await?;
+                let Some(req) = self.prepare_request(self.selected_request()) else {
+                    return Ok(());
+                };
+                let (req, variants) = match AcceptVariants::new(req.accept_variants()) {
+                    Some((first, variants)) => (first, Some(variants)),
+                    None => (req, None),
+                };
+                if self.send(req, self.list.selected()) {
+                    self.accept_variants = variants;
+                }
             }
             _ => {}
         }
         Ok(())
     }
 }
+
+fn write_log(path: &str, log: &ExchangeLog) -> Result<(), Box<dyn Error>> {
+    fs::write(path, log.to_json()?)?;
+    Ok(())
+}
+
+/// `METHOD url`, the text of the request matched by [`fuzzy_match`]
+pub fn request_line(req: &HttpRequest) -> String {
+    format!("{} {}", req.method, req.url)
+}
+
+/// Matches the characters of `query` in order, case insensitive. Consecutive matches and
+/// matches at the start of a word score higher. Returns the score and the char indices of
+/// `text` that were matched.
+pub fn fuzzy_match(text: &str, query: &str) -> Option<(i64, Vec<usize>)> {
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut positions = Vec::new();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+
+    for (i, c) in text.chars().enumerate() {
+        let Some(&q) = query.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(std::iter::once(q)) {
+            score += 1;
+            if positions.last().is_some_and(|&last| last + 1 == i) {
+                score += 5;
+            }
+            if previous.is_none_or(|p| !p.is_alphanumeric()) {
+                score += 3;
+            }
+            positions.push(i);
+            query.next();
+        }
+        previous = Some(c);
+    }
+
+    query.peek().is_none().then_some((score, positions))
+}
+
+/// Case insensitive check of `query` against all the fields of the request.
+/// `query` is expected to be lowercase.
+fn request_matches(req: &HttpRequest, query: &str) -> bool {
+    req.method.to_string().to_lowercase().contains(query)
+        || req.url.to_lowercase().contains(query)
+        || req
+            .headers
+            .iter()
+            .any(|(k, v)| k.to_lowercase().contains(query) || v.to_lowercase().contains(query))
+        || req.body.to_lowercase().contains(query)
+}
+
+/// How the response body is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// JSON bodies are indented
+    Rich,
+    /// Shown as received, which keeps large bodies responsive
+    Plain,
+}
+
+/// Bodies larger than `max_bytes` are shown plain unless rich rendering is forced
+fn render_mode(body_len: usize, max_bytes: usize, force_rich: bool) -> RenderMode {
+    if force_rich || body_len <= max_bytes {
+        RenderMode::Rich
+    } else {
+        RenderMode::Plain
+    }
+}
+
+/// Writes the response body to `path` encoded with `encoding`, or the received bytes for `raw`.
+/// Text bodies get `line_ending`
+pub fn save_body(
+    response: &Response,
+    path: &str,
+    encoding: &str,
+    line_ending: LineEnding,
+) -> Result<String, Box<dyn Error>> {
+    // Binary bodies are saved as they were received. `raw` keeps the
+    // original bytes of bodies shown transcoded, like CBOR
+    if response.binary || encoding.trim() == "raw" {
+        fs::write(path, &response.bytes)?;
+        return Ok(format!("Saved to {}", path));
+    }
+
+    let body = line_ending.apply(&response.body);
+    let encoded = encode_with_encoding(&body, encoding)
+        .ok_or_else(|| format!("unknown encoding {}", encoding))?;
+    fs::write(path, &encoded.bytes)?;
+
+    Ok(if encoded.unmappable {
+        format!(
+            "Saved to {}. Warning: some characters can't be encoded in {}",
+            path, encoded.encoding
+        )
+    } else {
+        format!("Saved to {}", path)
+    })
+}
+
+/// Byte ranges of the occurrences of `query` in `line`, ignoring ASCII case
+pub fn match_ranges(line: &str, query: &str) -> Vec<(usize, usize)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let (line, query) = (line.to_ascii_lowercase(), query.to_ascii_lowercase());
+    line.match_indices(&query)
+        .map(|(start, m)| (start, start + m.len()))
+        .collect()
+}
+
+/// Whether the body is JSON, or CBOR decoded to JSON
+fn is_json(response: &Response) -> bool {
+    response.cbor
+        || response
+            .content_type()
+            .is_some_and(|ct| ct.contains("json"))
+}
+
+/// Text of the response pane, with the headers matching `header_filter` only.
+/// `rich` indents JSON bodies
+fn format_response(response: &Response, header_filter: Option<&str>, rich: bool) -> String {
+    let mut formatted = None;
+    if let Some(query) = header_filter {
+        formatted = Some(filter_headers(response, query));
+    }
+    if rich && is_json(response) && !response.binary {
+        if let Ok(body) = indent_json(&response.body, DEFAULT_INDENT) {
+            formatted.get_or_insert_with(|| response.clone()).body = body;
+        }
+    }
+    let response = formatted.as_ref().unwrap_or(response);
+    let parts = response
+        .content_type()
+        .and_then(|ct| parse_multipart(&response.bytes, ct));
+    if let Some(parts) = parts {
+        let mut ret = format!("{}\n", response_head(response));
+        format_parts(&parts, "", rich, &mut ret);
+        ret
+    } else if is_event_stream(&response.headers) && !response.binary {
+        format_event_stream(response)
+    } else {
+        response.to_string()
+    }
+}
+
+/// Status line and headers of the response
+fn response_head(response: &Response) -> String {
+    let full = response.to_string();
+    // Headers can't contain empty lines
+    full.split_once("\n\n")
+        .map_or(full.as_str(), |(head, _)| head)
+        .to_string()
+}
+
+/// Start of the title line of each part of a multipart response
+const PART_TITLE: &str = "── Part ";
+
+/// Writes each part with its headers under a title numbered after `prefix`, e.g. `Part 2.1`.
+/// Nested multipart bodies are split too
+fn format_parts(parts: &[Part], prefix: &str, rich: bool, out: &mut String) {
+    for (i, part) in parts.iter().enumerate() {
+        let label = format!("{}{}", prefix, i + 1);
+        let content_type = part.header("content-type").unwrap_or("text/plain");
+        let _ = writeln!(out, "\n{}{} ─ {}", PART_TITLE, label, content_type);
+        for (name, value) in &part.headers {
+            let _ = writeln!(out, "{}: {}", name, value);
+        }
+        if let Some(nested) = part.parts() {
+            format_parts(&nested, &format!("{}.", label), rich, out);
+            continue;
+        }
+        out.push('\n');
+        match std::str::from_utf8(&part.body) {
+            Ok(text) if rich && content_type.contains("json") => out
+                .push_str(&indent_json(text, DEFAULT_INDENT).unwrap_or_else(|_| text.to_string())),
+            Ok(text) => out.push_str(text),
+            Err(_) => {
+                let _ = write!(out, "<binary part, {} bytes>", part.body.len());
+            }
+        }
+        out.push('\n');
+    }
+}
+
+/// Lines of the titles of the parts of a multipart response
+fn part_offsets(response: &str) -> Vec<u16> {
+    response
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with(PART_TITLE))
+        .map(|(i, _)| i as u16)
+        .collect()
+}
+
+/// Copy of the response with the headers whose name or value contains `query`, ignoring case
+fn filter_headers(response: &Response, query: &str) -> Response {
+    let query = query.to_lowercase();
+    let headers = response
+        .headers
+        .iter()
+        .filter(|(name, value)| {
+            name.as_str().contains(&query)
+                || String::from_utf8_lossy(value.as_bytes())
+                    .to_lowercase()
+                    .contains(&query)
+        })
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    Response {
+        headers,
+        ..response.clone()
+    }
+}
+
+/// Formats the response with each event of the body in a box titled with its type
+fn format_event_stream(response: &Response) -> String {
+    let mut ret = format!("{}\n", response_head(response));
+    for event in parse_events(&response.body) {
+        let mut title = event.event_type.unwrap_or_else(|| "message".to_string());
+        if let Some(id) = event.id {
+            title = format!("{} #{}", title, id);
+        }
+        let width = event
+            .data
+            .lines()
+            .map(|line| line.chars().count())
+            .chain([title.chars().count() + 2])
+            .max()
+            .unwrap_or_default();
+        let top = format!("─ {} ", title);
+        ret.push_str(&format!(
+            "\n┌{}{}┐\n",
+            top,
+            "─".repeat(width + 2 - top.chars().count())
+        ));
+        for line in event.data.lines() {
+            ret.push_str(&format!("│ {:<width$} │\n", line, width = width));
+        }
+        ret.push_str(&format!("└{}┘\n", "─".repeat(width + 2)));
+    }
+    ret
+}
+
+/// Content types with meaningful indentation
+fn is_structured(content_type: &str) -> bool {
+    ["json", "xml", "yaml", "html"]
+        .iter()
+        .any(|t| content_type.contains(t))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Section {
+    Headers,
+    Body,
+}
+
+/// Line offsets of the sections of a rendered response
+#[derive(Debug, PartialEq, Eq)]
+struct SectionOffsets {
+    headers: u16,
+    body: u16,
+}
+
+/// Headers start after the status line and the body after the first empty line.
+/// Offsets are clamped to the last line.
+fn section_offsets(response: &str) -> SectionOffsets {
+    let last = response.lines().count().saturating_sub(1) as u16;
+    let body = find_header_line(response, "").map_or(last, |line| line + 1);
+    SectionOffsets {
+        headers: 1.min(last),
+        body: body.min(last),
+    }
+}
+
+/// Finds the line starting with the header `name` (case insensitive).
+/// An empty `name` finds the first empty line, which precedes the body.
+fn find_header_line(response: &str, name: &str) -> Option<u16> {
+    response
+        .lines()
+        .position(|line| {
+            if name.is_empty() {
+                return line.is_empty();
+            }
+            line.split_once(':')
+                .is_some_and(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        })
+        .map(|i| i as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        exit_decision, filter_headers, find_header_line, format_event_stream, format_response,
+        fuzzy_match, part_offsets, render_mode, request_matches, section_offsets, AcceptVariants,
+        App, ExitDecision, Focus, Follow, RenderMode, SectionOffsets, SessionStats,
+    };
+    use crate::config::Config;
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+    use rq_core::{
+        parser::{parse, HttpFile},
+        request::Response,
+    };
+    use std::{
+        env, fs,
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    fn key(c: char) -> Event {
+        Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+    }
+
+    fn app(file: HttpFile) -> App {
+        App::new(
+            "test.http".to_string(),
+            file,
+            &Config::default(),
+            Default::default(),
+            Vec::new(),
+        )
+    }
+
+    fn response(headers: &[(&'static str, &'static str)], body: &str) -> Response {
+        Response {
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.parse().unwrap(), v.parse().unwrap()))
+                .collect(),
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Ticks until the pending request gets its response
+    async fn wait_response(app: &mut App) {
+        for _ in 0..500 {
+            app.tick();
+            if app.pending.is_none() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("no response");
+    }
+
+    #[tokio::test]
+    async fn test_failed_request_keeps_sending() {
+        // Nothing listens on the port once the listener is dropped
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .unwrap();
+        });
+
+        let file = parse(&format!(
+            "GET http://{}/ HTTP/1.1\n\n###\n\nGET http://{}/ HTTP/1.1\n\n",
+            closed, open
+        ))
+        .unwrap();
+        let mut app = app(file);
+        let enter = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let down = Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+
+        app.on_terminal_event(enter.clone()).await.unwrap();
+        wait_response(&mut app).await;
+        assert!(app.response.is_none());
+        assert!(app.request_failed(0));
+
+        app.on_terminal_event(down).await.unwrap();
+        app.on_terminal_event(enter).await.unwrap();
+        wait_response(&mut app).await;
+        assert_eq!(app.response.as_ref().unwrap().body, "ok");
+        assert!(app.request_failed(0));
+        assert!(!app.request_failed(1));
+    }
+
+    #[tokio::test]
+    async fn test_send_while_pending() {
+        // Answers after 200ms, while the second request is refused
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).unwrap();
+            thread::sleep(Duration::from_millis(200));
+            stream
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+        });
+
+        let file = parse(&format!(
+            "# @expect-status 404\nGET http://{}/ HTTP/1.1\n\n###\n\n\
+             # @expect-status 200\nGET http://{}/ HTTP/1.1\n\n",
+            addr, addr
+        ))
+        .unwrap();
+        let mut app = app(file);
+        let enter = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let down = Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+
+        app.on_terminal_event(enter.clone()).await.unwrap();
+        app.on_terminal_event(down).await.unwrap();
+        app.on_terminal_event(enter).await.unwrap();
+        assert_eq!(app.message.as_deref(), Some("A request is already pending"));
+        assert_eq!(app.pending.map(|(index, _)| index), Some(Some(0)));
+
+        // The response goes to the request that was sent
+        wait_response(&mut app).await;
+        let summary = app.assertion_summary();
+        assert_eq!((summary.passed, summary.failed), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_missing_body_file_is_not_sent() {
+        let file = parse("POST http://127.0.0.1:9/ HTTP/1.1\n\n< ./missing-body.json\n").unwrap();
+        let mut app = app(file);
+        let enter = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        app.on_terminal_event(enter).await.unwrap();
+        assert!(app.pending.is_none());
+        assert_eq!(
+            app.message.as_deref(),
+            Some("Body file not found: ./missing-body.json")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_split_ratio_is_saved_on_tick() {
+        let path = env::temp_dir()
+            .join(format!("rq-config-{}", std::process::id()))
+            .join("config.toml");
+        let config = Config {
+            panel_split_ratio: 70,
+            tick_rate_ms: 100,
+            ..Default::default()
+        };
+        let file = parse("GET https://api.dev HTTP/1.1\n\n").unwrap();
+        let mut app = App::new(
+            "test.http".to_string(),
+            file,
+            &config,
+            Default::default(),
+            Vec::new(),
+        );
+        app.config.1 = Some(path.clone());
+        assert_eq!(app.list_percent, 70);
+
+        for _ in 0..3 {
+            app.on_terminal_event(key('>')).await.unwrap();
+        }
+        assert_eq!(app.list_percent, 80);
+        // Written on the next tick only
+        assert!(!path.exists());
+        app.save_config();
+        let saved: Config = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.panel_split_ratio, 80);
+        assert_eq!(saved.tick_rate_ms, 100);
+
+        // Unchanged settings are not written again
+        fs::remove_file(&path).unwrap();
+        app.save_config();
+        assert!(!path.exists());
+
+        app.on_terminal_event(key('<')).await.unwrap();
+        app.save_config();
+        let saved: Config = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.panel_split_ratio, 75);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_request_matches() {
+        let file = parse(
+            "POST https://api.dev/users HTTP/1.1\nAuthorization: Bearer Token\n\n{ \"name\": \"rq\" }",
+        )
+        .unwrap();
+        let req = &file.requests[0];
+        for query in [
+            "post",
+            "api.dev/users",
+            "authorization",
+            "bearer token",
+            "\"rq\"",
+        ] {
+            assert!(request_matches(req, query), "{}", query);
+        }
+        assert!(!request_matches(req, "delete"));
+    }
+
+    #[test]
+    fn test_find_header_line() {
+        let response = "HTTP/1.1 200 OK\ncontent-type: text/plain\nx-request-id: 42\n\nbody: text";
+        assert_eq!(find_header_line(response, "X-Request-Id"), Some(2));
+        assert_eq!(find_header_line(response, "content-type"), Some(1));
+        assert_eq!(find_header_line(response, ""), Some(3));
+        assert_eq!(find_header_line(response, "x-missing"), None);
+    }
+
+    #[test]
+    fn test_exit_decision() {
+        assert_eq!(
+            exit_decision(false, false, false, false),
+            ExitDecision::Exit
+        );
+        assert_eq!(exit_decision(false, false, false, true), ExitDecision::Exit);
+        assert_eq!(
+            exit_decision(true, false, false, false),
+            ExitDecision::Confirm
+        );
+        assert_eq!(
+            exit_decision(true, false, false, true),
+            ExitDecision::Confirm
+        );
+        assert_eq!(
+            exit_decision(true, false, true, false),
+            ExitDecision::Confirm
+        );
+        assert_eq!(exit_decision(true, false, true, true), ExitDecision::Exit);
+        // Edited bodies are lost on exit too
+        assert_eq!(
+            exit_decision(false, true, false, false),
+            ExitDecision::Confirm
+        );
+        assert_eq!(exit_decision(false, true, true, true), ExitDecision::Exit);
+    }
+
+    #[test]
+    fn test_format_event_stream() {
+        let response = response(
+            &[("content-type", "text/event-stream")],
+            ": keep-alive\n\nevent: tick\nid: 7\ndata: a\ndata: long line\n\ndata: x\n\n",
+        );
+        assert_eq!(
+            format_event_stream(&response),
+            "HTTP/1.1 200 OK
+content-type: text/event-stream
+
+┌─ tick #7 ─┐
+│ a         │
+│ long line │
+└───────────┘
+
+┌─ message ─┐
+│ x         │
+└───────────┘
+"
+        );
+    }
+
+    #[test]
+    fn test_section_offsets() {
+        let response =
+            "HTTP/1.1 200 OK\ncontent-type: text/plain\nx-request-id: 42\n\nline 1\nline 2";
+        assert_eq!(
+            section_offsets(response),
+            SectionOffsets {
+                headers: 1,
+                body: 4
+            }
+        );
+
+        // No body
+        let response = "HTTP/1.1 204 No Content\nx-request-id: 42\n";
+        assert_eq!(
+            section_offsets(response),
+            SectionOffsets {
+                headers: 1,
+                body: 1
+            }
+        );
+
+        assert_eq!(
+            section_offsets(""),
+            SectionOffsets {
+                headers: 0,
+                body: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_follow() {
+        let follow = Follow::Off.toggle();
+        assert_eq!(follow, Follow::On);
+        // Pinned to the bottom as the content grows
+        assert_eq!(follow.scroll(0, 10), 10);
+        assert_eq!(follow.scroll(10, 25), 25);
+
+        // Scrolling up pauses
+        let follow = follow.scrolled(24, 25);
+        assert_eq!(follow, Follow::Paused);
+        assert_eq!(follow.scroll(24, 40), 24);
+        // Still paused as the content grows
+        let follow = follow.scrolled(25, 40);
+        assert_eq!(follow, Follow::Paused);
+
+        // Back to the bottom resumes
+        let follow = follow.scrolled(40, 40);
+        assert_eq!(follow, Follow::On);
+        assert_eq!(follow.scroll(40, 55), 55);
+
+        assert_eq!(follow.toggle(), Follow::Off);
+        assert_eq!(Follow::Paused.toggle(), Follow::Off);
+        assert_eq!(Follow::Off.scrolled(40, 40), Follow::Off);
+        assert_eq!(Follow::Off.scroll(3, 40), 3);
+    }
+
+    #[test]
+    fn test_render_mode() {
+        assert_eq!(render_mode(10, 100, false), RenderMode::Rich);
+        assert_eq!(render_mode(100, 100, false), RenderMode::Rich);
+        assert_eq!(render_mode(101, 100, false), RenderMode::Plain);
+        assert_eq!(render_mode(101, 100, true), RenderMode::Rich);
+
+        let response = response(&[("content-type", "application/json")], r#"{"id":1}"#);
+        assert!(format_response(&response, None, true).ends_with("{\n  \"id\": 1\n}"));
+        assert!(format_response(&response, None, false).ends_with(r#"{"id":1}"#));
+    }
+
+    #[tokio::test]
+    async fn test_search_response() {
+        let file = parse("GET https://api.dev HTTP/1.1\n\n").unwrap();
+        let mut app = app(file);
+        app.focus = Focus::Response;
+        app.response = Some(response(&[], "ok\nerror: a\nok\nERROR: b"));
+        app.refresh_response();
+        let enter = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+        let esc = Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+
+        // Matches are highlighted as the query is typed
+        app.on_terminal_event(key('/')).await.unwrap();
+        app.on_terminal_event(key('b')).await.unwrap();
+        assert_eq!(app.response_query.as_deref(), Some("b"));
+        assert_eq!(app.response_scroll, 5);
+        app.on_terminal_event(key('x')).await.unwrap();
+        assert!(app.response_matches.is_empty());
+        assert_eq!(app.response_scroll, 0);
+        // Esc drops the highlights and goes back to where the search started
+        app.on_terminal_event(key('b')).await.unwrap();
+        app.on_terminal_event(esc).await.unwrap();
+        assert_eq!(app.response_query, None);
+        assert_eq!(app.response_scroll, 0);
+
+        app.on_terminal_event(key('/')).await.unwrap();
+        for c in "Error".chars() {
+            app.on_terminal_event(key(c)).await.unwrap();
+        }
+        app.on_terminal_event(enter.clone()).await.unwrap();
+        // After the status line and the empty line
+        assert_eq!(app.response_scroll, 3);
+        assert_eq!(app.message.as_deref(), Some("Match 1 of 2"));
+        app.on_terminal_event(key('n')).await.unwrap();
+        assert_eq!(app.response_scroll, 5);
+        app.on_terminal_event(key('n')).await.unwrap();
+        assert_eq!(app.response_scroll, 3);
+        app.on_terminal_event(key('N')).await.unwrap();
+        assert_eq!(app.response_scroll, 5);
+
+        // The prompt starts with the previous query
+        app.on_terminal_event(key('/')).await.unwrap();
+        app.on_terminal_event(key('x')).await.unwrap();
+        app.on_terminal_event(enter).await.unwrap();
+        assert_eq!(app.message.as_deref(), Some("No matches for Errorx"));
+        assert_eq!(app.response_query, None);
+        assert_eq!(app.response_scroll, 5);
+    }
+
+    #[tokio::test]
+    async fn test_toggle_raw_response() {
+        let file = parse("GET https://api.dev HTTP/1.1\n\n").unwrap();
+        let mut app = app(file);
+        app.focus = Focus::Response;
+        app.response = Some(response(
+            &[("content-type", "application/json")],
+            r#"{"id":1}"#,
+        ));
+        app.refresh_response();
+        assert!(app.json_body);
+        assert!(app.response_buffer.ends_with("{\n  \"id\": 1\n}"));
+
+        app.on_terminal_event(key('r')).await.unwrap();
+        assert!(!app.json_body);
+        assert!(app.response_buffer.ends_with(r#"{"id":1}"#));
+
+        app.on_terminal_event(key('r')).await.unwrap();
+        app.response.as_mut().unwrap().body = r#"{"id":"#.to_string();
+        app.refresh_response();
+        assert!(!app.json_body);
+        assert!(app.response_buffer.ends_with(r#"{"id":"#));
+        assert_eq!(
+            app.message.as_deref(),
+            Some("Invalid JSON body, shown as received")
+        );
+    }
+
+    #[test]
+    fn test_format_multipart() {
+        let body = "--b\r\nContent-Type: application/json\r\n\r\n{\"id\":1}\r\n--b\r\nContent-Type: multipart/alternative; boundary=i\r\n\r\n--i\r\n\r\nhello\r\n--i--\r\n--b--\r\n";
+        let response = Response {
+            bytes: body.as_bytes().to_vec().into(),
+            ..response(&[("content-type", "multipart/mixed; boundary=b")], body)
+        };
+        let formatted = format_response(&response, None, true);
+        assert_eq!(
+            formatted,
+            "HTTP/1.1 200 OK
+content-type: multipart/mixed; boundary=b
+
+── Part 1 ─ application/json
+Content-Type: application/json
+
+{
+  \"id\": 1
+}
+
+── Part 2 ─ multipart/alternative; boundary=i
+Content-Type: multipart/alternative; boundary=i
+
+── Part 2.1 ─ text/plain
+
+hello
+"
+        );
+        assert_eq!(part_offsets(&formatted), [3, 10, 13]);
+    }
+
+    #[test]
+    fn test_filter_headers() {
+        let response = response(
+            &[
+                ("content-type", "application/json"),
+                ("x-request-id", "42"),
+                ("cache-control", "no-cache"),
+                ("x-cache", "HIT"),
+            ],
+            "{}",
+        );
+        let names = |query: &str| -> Vec<String> {
+            filter_headers(&response, query)
+                .headers
+                .keys()
+                .map(|k| k.to_string())
+                .collect()
+        };
+
+        assert_eq!(names("CACHE"), ["cache-control", "x-cache"]);
+        assert_eq!(names("json"), ["content-type"]);
+        assert_eq!(names("hit"), ["x-cache"]);
+        assert!(names("missing").is_empty());
+        assert_eq!(names("").len(), 4);
+        assert_eq!(filter_headers(&response, "x-").body, "{}");
+    }
+
+    #[test]
+    fn test_accept_variants() {
+        let input = "# @accept-matrix json,xml,html\nGET https://api.dev HTTP/1.1\n\n";
+        let req = &parse(input).unwrap().requests[0];
+        let (first, mut variants) = AcceptVariants::new(req.accept_variants()).unwrap();
+        assert_eq!(first.headers["Accept"], "application/json");
+        assert_eq!(variants.queued.len(), 2);
+
+        for (_, variant) in req.accept_variants() {
+            variants.responses.push((variant, Ok(Response::default())));
+        }
+        assert_eq!(variants.label(), "Accept: application/json (1/3)");
+        variants.cycle(-1);
+        assert_eq!(variants.label(), "Accept: text/html (3/3)");
+        variants.cycle(1);
+        variants.cycle(1);
+        assert_eq!(variants.label(), "Accept: application/xml (2/3)");
+
+        let req = &parse("GET https://api.dev HTTP/1.1\n\n").unwrap().requests[0];
+        assert!(AcceptVariants::new(req.accept_variants()).is_none());
+    }
+
+    #[test]
+    fn test_session_stats() {
+        let mut stats = SessionStats::default();
+        assert_eq!(stats.average(), None);
+        assert_eq!(stats.median(), None);
+        assert_eq!(stats.success_rate(), 0.0);
+
+        let ok = |ms| Response {
+            bytes: "hello".into(),
+            elapsed: Duration::from_millis(ms),
+            ..Default::default()
+        };
+        let not_found = Response {
+            status: 404.try_into().unwrap(),
+            elapsed: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let error = "connection refused".to_string();
+        stats.record("/a", Ok(&ok(30)));
+        stats.record("/b", Ok(&not_found));
+        stats.record("/c", Err(&error));
+        stats.record("/d", Ok(&ok(20)));
+
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.success, 2);
+        assert_eq!(stats.error, 1);
+        assert_eq!(stats.total_bytes, 10);
+        assert_eq!(stats.success_rate(), 50.0);
+        // Requests without a response aren't timed
+        assert_eq!(stats.timings.len(), 3);
+        assert_eq!(stats.average(), Some(Duration::from_millis(20)));
+        assert_eq!(stats.median(), Some(Duration::from_millis(20)));
+        assert_eq!(
+            stats.slowest,
+            Some(("/a".to_string(), Duration::from_millis(30)))
+        );
+        assert_eq!(
+            stats.fastest,
+            Some(("/b".to_string(), Duration::from_millis(10)))
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        let text = "GET https://api.dev/users";
+        let (_, positions) = fuzzy_match(text, "gusr").unwrap();
+        let matched: String = positions
+            .iter()
+            .map(|&i| text.chars().nth(i).unwrap().to_ascii_lowercase())
+            .collect();
+        assert_eq!(matched, "gusr");
+        assert_eq!(positions, vec![0, 20, 21, 23]);
+
+        assert!(fuzzy_match(text, "users").is_some());
+        assert!(fuzzy_match(text, "sresu").is_none());
+        assert!(fuzzy_match(text, "post").is_none());
+
+        // Consecutive and word start matches rank higher
+        let (contiguous, _) = fuzzy_match(text, "users").unwrap();
+        let (scattered, _) = fuzzy_match("GET /u/s/e/r/s", "users").unwrap();
+        assert!(contiguous > scattered);
+    }
+}
@@ -0,0 +1,209 @@
+use std::{ffi::OsString, time::Duration};
+
+use lexopt::prelude::*;
+
+use crate::list::ListFormat;
+
+pub const USAGE: &str =
+    "Usage: rq-cli [--tick-rate <ms>] [--env <name>] [--ascii] [--log <file>] [--cookie-jar <file>] [--separator <prefix>] [--list [--format plain|tsv]] [--run <indices> | --request <index> | --headless [--trace-vars] [--output-dir <dir>]] [--serve <addr>] [--idle <duration>] [--timeout <duration>] <file | ->\n       rq-cli --replay <log>";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    pub file_path: String,
+    /// Overrides `Config::tick_rate_ms`
+    pub tick_rate: Option<u64>,
+    /// Environment active at startup
+    pub env: Option<String>,
+    /// Draw the UI with ASCII symbols only
+    pub ascii: bool,
+    /// File to write the executed exchanges to
+    pub log: Option<String>,
+    /// Exchange log to browse instead of a requests file
+    pub replay: Option<String>,
+    /// Cookie file to seed the cookie jar with
+    pub cookie_jar: Option<String>,
+    /// Prefix of the lines delimiting requests, `###` by default
+    pub separator: Option<String>,
+    /// Print the requests instead of starting the TUI
+    pub list: bool,
+    pub list_format: ListFormat,
+    /// Requests to send without starting the TUI, e.g. `1,3,5` or `2-4`
+    pub run: Option<String>,
+    /// Send every request without starting the TUI, like `--run` with all the indices.
+    /// Also set by `--no-tui`
+    pub headless: bool,
+    /// Print what each placeholder of the `--run` requests resolved to
+    pub trace_vars: bool,
+    /// Directory the `--run` responses are saved to, one numbered file per request
+    pub output_dir: Option<String>,
+    /// Address to serve the recorded responses at, e.g. `:8080`
+    pub serve: Option<String>,
+    /// Inactivity after which the TUI saves its state and exits, e.g. `30m`
+    pub idle: Option<Duration>,
+    /// Overall timeout of the requests without `# @timeout`, e.g. `30s`
+    pub timeout: Option<Duration>,
+}
+
+pub fn parse_args<I>(args: I) -> Result<Args, lexopt::Error>
+where
+    I: IntoIterator,
+    I::Item: Into<OsString>,
+{
+    let mut file_path = None;
+    let mut request = None;
+    let mut ret = Args::default();
+
+    let mut parser = lexopt::Parser::from_iter(args);
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Long("tick-rate") => ret.tick_rate = Some(parser.value()?.parse()?),
+            Long("env") => ret.env = Some(parser.value()?.string()?),
+            Long("ascii") => ret.ascii = true,
+            Long("log") => ret.log = Some(parser.value()?.string()?),
+            Long("replay") => ret.replay = Some(parser.value()?.string()?),
+            Long("cookie-jar") => ret.cookie_jar = Some(parser.value()?.string()?),
+            Long("separator") => {
+                let separator = parser.value()?.string()?;
+                if separator.trim().is_empty() {
+                    return Err("the separator can't be empty".into());
+                }
+                ret.separator = Some(separator);
+            }
+            Long("list") => ret.list = true,
+            Long("format") => ret.list_format = parser.value()?.parse()?,
+            Long("run") => ret.run = Some(parser.value()?.string()?),
+            Long("request") => request = Some(parser.value()?.parse::<usize>()?),
+            Long("headless") | Long("no-tui") => ret.headless = true,
+            Long("trace-vars") => ret.trace_vars = true,
+            Long("output-dir") => ret.output_dir = Some(parser.value()?.string()?),
+            Long("serve") => {
+                let addr = parser.value()?.string()?;
+                // `:8080` listens on localhost only
+                ret.serve = Some(match addr.strip_prefix(':') {
+                    Some(port) => format!("127.0.0.1:{}", port),
+                    None => addr,
+                });
+            }
+            Long("idle") => ret.idle = Some(parse_duration(&parser.value()?.string()?)?),
+            Long("timeout") => ret.timeout = Some(parse_duration(&parser.value()?.string()?)?),
+            Value(value) if file_path.is_none() => file_path = Some(value.string()?),
+            _ => return Err(arg.unexpected()),
+        }
+    }
+
+    if let Some(index) = request {
+        if ret.run.is_some() {
+            return Err("--request can't be used with --run".into());
+        }
+        // A single index, checked against the requests by `run::parse_indices`
+        ret.run = Some(index.to_string());
+    }
+    if ret.output_dir.is_some() && ret.run.is_none() && !ret.headless {
+        return Err("--output-dir requires --run or --headless".into());
+    }
+
+    // A replayed log doesn't need a requests file
+    ret.file_path = file_path
+        .or_else(|| ret.replay.clone())
+        .ok_or("no files provided")?;
+    Ok(ret)
+}
+
+/// Parses durations like `90s`, `30m` or `2h`. Plain numbers are seconds
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(at) => value.split_at(at),
+        None => (value, "s"),
+    };
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => return Err(format!("invalid duration unit in '{}'", value)),
+    };
+    match number.parse::<u64>().ok().and_then(|n| n.checked_mul(secs)) {
+        Some(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+        _ => Err(format!("invalid duration '{}'", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_args, parse_duration, Args};
+    use crate::list::ListFormat;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse_args(["rq", "test.http", "--tick-rate", "100"]).unwrap();
+        assert_eq!(
+            args,
+            Args {
+                file_path: "test.http".to_string(),
+                tick_rate: Some(100),
+                ..Default::default()
+            }
+        );
+
+        let args = parse_args(["rq", "test.http", "--env", "prod"]).unwrap();
+        assert_eq!(args.env.as_deref(), Some("prod"));
+        assert!(parse_args(["rq", "test.http", "--ascii"]).unwrap().ascii);
+
+        let args = parse_args(["rq", "--replay", "session.json"]).unwrap();
+        assert_eq!(args.replay.as_deref(), Some("session.json"));
+        assert_eq!(args.file_path, "session.json");
+
+        let args = parse_args(["rq", "test.http", "--cookie-jar", "cookies.txt"]).unwrap();
+        assert_eq!(args.cookie_jar.as_deref(), Some("cookies.txt"));
+
+        let args = parse_args(["rq", "test.http", "--separator", "---"]).unwrap();
+        assert_eq!(args.separator.as_deref(), Some("---"));
+        assert!(parse_args(["rq", "test.http", "--separator", " "]).is_err());
+
+        let args = parse_args(["rq", "--list", "--format", "tsv", "test.http"]).unwrap();
+        assert!(args.list);
+        assert_eq!(args.list_format, ListFormat::Tsv);
+        assert!(parse_args(["rq", "test.http", "--format", "xml"]).is_err());
+
+        let args = parse_args(["rq", "test.http", "--run", "1,3-4"]).unwrap();
+        assert_eq!(args.run.as_deref(), Some("1,3-4"));
+        assert!(!args.trace_vars);
+        let args = parse_args(["rq", "test.http", "--run", "0", "--trace-vars"]).unwrap();
+        assert!(args.trace_vars);
+        let args = parse_args(["rq", "--headless", "test.http", "--output-dir", "out"]).unwrap();
+        assert!(args.headless);
+        assert_eq!(args.output_dir.as_deref(), Some("out"));
+        assert!(parse_args(["rq", "test.http", "--output-dir", "out"]).is_err());
+        assert!(
+            parse_args(["rq", "--no-tui", "test.http"])
+                .unwrap()
+                .headless
+        );
+        let args = parse_args(["rq", "test.http", "--request", "2"]).unwrap();
+        assert_eq!(args.run.as_deref(), Some("2"));
+        assert!(parse_args(["rq", "test.http", "--request", "1-2"]).is_err());
+        assert!(parse_args(["rq", "test.http", "--run", "0-3", "--request", "2"]).is_err());
+        assert!(parse_args(["rq", "test.http", "--request", "2", "--run", "0-3"]).is_err());
+
+        let args = parse_args(["rq", "test.http", "--serve", ":8080"]).unwrap();
+        assert_eq!(args.serve.as_deref(), Some("127.0.0.1:8080"));
+        let args = parse_args(["rq", "test.http", "--serve", "0.0.0.0:80"]).unwrap();
+        assert_eq!(args.serve.as_deref(), Some("0.0.0.0:80"));
+
+        let args = parse_args(["rq", "test.http", "--idle", "30m"]).unwrap();
+        assert_eq!(args.idle, Some(Duration::from_secs(30 * 60)));
+        assert!(parse_args(["rq", "test.http", "--idle", "soon"]).is_err());
+        let args = parse_args(["rq", "--timeout", "30", "test.http"]).unwrap();
+        assert_eq!(args.timeout, Some(Duration::from_secs(30)));
+        assert!(parse_args(["rq", "test.http", "--timeout", "0s"]).is_err());
+        assert!(parse_args(["rq", "test.http", "--timeout", "18446744073709551615h"]).is_err());
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("5d").is_err());
+
+        assert!(parse_args(["rq"]).is_err());
+        assert!(parse_args(["rq", "test.http", "--tick-rate", "fast"]).is_err());
+        assert!(parse_args(["rq", "test.http", "--unknown"]).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    io,
+    process::{Command, Stdio},
+};
+
+/// Whether `url` can be opened in a browser, only http(s) urls are
+pub fn is_openable(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    ["http://", "https://"]
+        .iter()
+        .any(|scheme| url.len() > scheme.len() && url.starts_with(scheme))
+}
+
+/// Program and arguments opening a url with the default browser of `os`, see [`std::env::consts::OS`]
+fn open_command(os: &str) -> (&'static str, &'static [&'static str]) {
+    match os {
+        "macos" => ("open", &[]),
+        // The empty argument is the window title, otherwise a quoted url would be taken as one
+        "windows" => ("cmd", &["/C", "start", ""]),
+        _ => ("xdg-open", &[]),
+    }
+}
+
+/// Opens `url` in the default browser without waiting for it
+pub fn open(url: &str) -> io::Result<()> {
+    if !is_openable(url) {
+        return Err(io::Error::other(format!("{} is not an http(s) url", url)));
+    }
+    let (program, args) = open_command(std::env::consts::OS);
+    Command::new(program)
+        .args(args)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_openable, open, open_command};
+
+    #[test]
+    fn test_is_openable() {
+        assert!(is_openable("https://example.com/login"));
+        assert!(is_openable("HTTP://localhost:8080"));
+        assert!(!is_openable("/users"));
+        assert!(!is_openable("ftp://example.com"));
+        assert!(!is_openable("file:///etc/passwd"));
+        assert!(!is_openable("https://"));
+        assert!(open("javascript:alert(1)").is_err());
+    }
+
+    #[test]
+    fn test_open_command() {
+        assert_eq!(open_command("macos"), ("open", &[][..]));
+        assert_eq!(open_command("windows"), ("cmd", &["/C", "start", ""][..]));
+        assert_eq!(open_command("linux"), ("xdg-open", &[][..]));
+        assert_eq!(open_command("freebsd"), ("xdg-open", &[][..]));
+    }
+}
@@ -0,0 +1,47 @@
+use rq_core::parser::{HttpMethod, HttpRequest};
+
+/// Builds a one-off request from a command line like `get https://example.com`.
+/// The rest of the line after the url is sent as the body.
+pub fn parse_command(line: &str) -> Result<HttpRequest, String> {
+    let line = line.trim();
+    let (verb, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let method = HttpMethod::iterator()
+        .find(|method| method.to_string().eq_ignore_ascii_case(verb))
+        .ok_or_else(|| format!("unknown command: {}", verb))?;
+
+    let rest = rest.trim_start();
+    let (url, body) = rest.split_once(' ').unwrap_or((rest, ""));
+    if url.is_empty() {
+        return Err(format!("usage: {} <url> [body]", verb));
+    }
+
+    Ok(HttpRequest::builder()
+        .method(method.clone())
+        .url(url)
+        .body(body.trim())
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_command;
+    use rq_core::parser::HttpMethod;
+
+    #[test]
+    fn test_parse_command() {
+        let req = parse_command("get https://example.com").unwrap();
+        assert!(matches!(req.method, HttpMethod::Get));
+        assert_eq!(req.url, "https://example.com");
+        assert_eq!(req.version, "1.1");
+        assert!(req.body.is_empty());
+
+        let req = parse_command("POST https://example.com/users {\"name\": \"rq\"}").unwrap();
+        assert!(matches!(req.method, HttpMethod::Post));
+        assert_eq!(req.url, "https://example.com/users");
+        assert_eq!(req.body, "{\"name\": \"rq\"}");
+
+        assert!(parse_command("fetch https://example.com").is_err());
+        assert!(parse_command("delete").is_err());
+        assert!(parse_command("").is_err());
+    }
+}
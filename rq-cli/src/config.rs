@@ -0,0 +1,122 @@
+use rq_core::{encoding::LineEnding, request::ClientConfig};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Bounds of [`Config::panel_split_ratio`]
+pub const SPLIT_RATIO_RANGE: (u16, u16) = (20, 80);
+
+/// User configuration read from `<config dir>/rq/config.toml`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// `User-Agent` sent by requests without a `# @user-agent` annotation or header
+    pub default_user_agent: Option<String>,
+    /// Trim leading whitespace when wrapping responses. When not set, structured
+    /// content (JSON, XML, ...) keeps its indentation and anything else is trimmed
+    pub trim_response_whitespace: Option<bool>,
+    /// Interval in milliseconds between UI updates when there are no events
+    pub tick_rate_ms: u64,
+    /// Prefix request methods with Nerd Font icons
+    pub use_nerd_font_icons: bool,
+    /// Fixed `Host` header for requests that don't define one
+    pub host_header: Option<String>,
+    /// Show a vim-style mode indicator and enable the `i`/`v` modes
+    pub vim_mode: bool,
+    /// Percentage of the screen used by the requests list, adjusted with `<` and `>`
+    pub panel_split_ratio: u16,
+    /// Indent JSON request bodies in the requests list. The body is sent as written
+    pub pretty_request_body: bool,
+    /// Line endings of saved text responses. Binary and `raw` saves keep the received bytes
+    pub save_line_endings: LineEnding,
+    /// Response bodies larger than this many bytes are shown as received, without formatting
+    pub rich_render_max_bytes: usize,
+    /// Color the body of 4xx and 5xx responses by status class
+    pub tint_response_body: bool,
+    /// Headers and query parameters whose values are hidden in copied snippets, ignoring case
+    pub redact: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_user_agent: None,
+            trim_response_whitespace: None,
+            tick_rate_ms: 250,
+            use_nerd_font_icons: false,
+            host_header: None,
+            vim_mode: false,
+            panel_split_ratio: 50,
+            pretty_request_body: false,
+            save_line_endings: LineEnding::Preserve,
+            rich_render_max_bytes: 1024 * 1024,
+            tint_response_body: false,
+            redact: [
+                "authorization",
+                "proxy-authorization",
+                "cookie",
+                "set-cookie",
+                "x-api-key",
+                "api_key",
+                "access_token",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+impl Config {
+    pub fn dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rq"))
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        Self::dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Requests file of the favorites available in every session
+    pub fn favorites_path() -> Option<PathBuf> {
+        Self::dir().map(|dir| dir.join("favorites.http"))
+    }
+
+    /// Directory containing the `.http` snippets for the snippet picker
+    pub fn snippets_dir() -> Option<PathBuf> {
+        Self::dir().map(|dir| dir.join("snippets"))
+    }
+
+    /// Loads the config file, falling back to defaults when it doesn't exist
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        match Self::path() {
+            Some(path) if path.exists() => Ok(toml::from_str(&fs::read_to_string(path)?)?),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Writes the config to `path`. Comments of an existing file are not kept
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// [`Config::panel_split_ratio`] within [`SPLIT_RATIO_RANGE`]
+    pub fn split_ratio(&self) -> u16 {
+        let (min, max) = SPLIT_RATIO_RANGE;
+        self.panel_split_ratio.clamp(min, max)
+    }
+
+    pub fn client_config(&self) -> ClientConfig {
+        ClientConfig {
+            default_user_agent: self.default_user_agent.clone(),
+            host: self.host_header.clone(),
+            ..Default::default()
+        }
+    }
+}
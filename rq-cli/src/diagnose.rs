@@ -0,0 +1,154 @@
+use rq_core::{parser::HttpRequest, request::Session};
+use tokio::sync::mpsc::{channel, Receiver};
+
+use std::time::{Duration, Instant};
+
+/// Times a request is sent to diagnose a flaky endpoint
+pub const RUNS: usize = 20;
+
+/// Result of one run, `status` is `None` when the request failed without a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub status: Option<u16>,
+    pub elapsed: Duration,
+}
+
+/// Latencies of the runs that ended with the same status
+#[derive(Debug, PartialEq, Eq)]
+pub struct StatusSummary {
+    pub status: Option<u16>,
+    pub count: usize,
+    pub min: Duration,
+    pub max: Duration,
+    pub avg: Duration,
+}
+
+/// Groups the outcomes by status, ordered by status with errors last
+pub fn summarize(outcomes: &[Outcome]) -> Vec<StatusSummary> {
+    let mut statuses: Vec<Option<u16>> = outcomes.iter().map(|o| o.status).collect();
+    statuses.sort_by_key(|status| status.unwrap_or(u16::MAX));
+    statuses.dedup();
+    statuses
+        .into_iter()
+        .map(|status| {
+            let timings: Vec<Duration> = outcomes
+                .iter()
+                .filter(|o| o.status == status)
+                .map(|o| o.elapsed)
+                .collect();
+            StatusSummary {
+                status,
+                count: timings.len(),
+                min: timings.iter().copied().min().unwrap_or_default(),
+                max: timings.iter().copied().max().unwrap_or_default(),
+                avg: timings.iter().sum::<Duration>() / timings.len() as u32,
+            }
+        })
+        .collect()
+}
+
+/// Repeated runs of a request, filled as the outcomes arrive
+pub struct Diagnosis {
+    pub url: String,
+    pub outcomes: Vec<Outcome>,
+    rx: Receiver<Outcome>,
+}
+
+impl Diagnosis {
+    /// Sends `req` [`RUNS`] times, one after the other. The runs stop once the diagnosis is dropped
+    pub fn start(req: HttpRequest, session: Session) -> Self {
+        let (tx, rx) = channel(RUNS);
+        let url = req.url.clone();
+        rq_core::runtime::spawn(async move {
+            for _ in 0..RUNS {
+                let sent = Instant::now();
+                let status = session
+                    .execute(&req)
+                    .await
+                    .ok()
+                    .map(|res| res.status.as_u16());
+                let outcome = Outcome {
+                    status,
+                    elapsed: sent.elapsed(),
+                };
+                if tx.send(outcome).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            url,
+            outcomes: Vec::new(),
+            rx,
+        }
+    }
+
+    /// Collects the outcomes received since the last call
+    pub fn poll(&mut self) {
+        while let Ok(outcome) = self.rx.try_recv() {
+            self.outcomes.push(outcome);
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.outcomes.len() == RUNS
+    }
+
+    /// Whether the runs ended with different statuses
+    pub fn flaky(&self) -> bool {
+        self.outcomes
+            .iter()
+            .any(|o| o.status != self.outcomes[0].status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{summarize, Outcome, StatusSummary};
+    use std::time::Duration;
+
+    fn outcome(status: Option<u16>, ms: u64) -> Outcome {
+        Outcome {
+            status,
+            elapsed: Duration::from_millis(ms),
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let outcomes = [
+            outcome(Some(200), 40),
+            outcome(None, 10_000),
+            outcome(Some(500), 900),
+            outcome(Some(200), 60),
+            outcome(Some(200), 20),
+        ];
+        assert_eq!(
+            summarize(&outcomes),
+            [
+                StatusSummary {
+                    status: Some(200),
+                    count: 3,
+                    min: Duration::from_millis(20),
+                    max: Duration::from_millis(60),
+                    avg: Duration::from_millis(40),
+                },
+                StatusSummary {
+                    status: Some(500),
+                    count: 1,
+                    min: Duration::from_millis(900),
+                    max: Duration::from_millis(900),
+                    avg: Duration::from_millis(900),
+                },
+                StatusSummary {
+                    status: None,
+                    count: 1,
+                    min: Duration::from_millis(10_000),
+                    max: Duration::from_millis(10_000),
+                    avg: Duration::from_millis(10_000),
+                },
+            ]
+        );
+        assert!(summarize(&[]).is_empty());
+    }
+}
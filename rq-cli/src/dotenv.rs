@@ -0,0 +1,50 @@
+use rq_core::variables::Variables;
+
+use std::{env, fs, path::Path};
+
+/// Parses the `NAME=value` lines of a `.env` file. Comments, empty lines and an `export `
+/// prefix are ignored, and values can be wrapped in single or double quotes
+pub fn parse_dotenv(content: &str) -> Variables {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = line.split_once('=')?;
+            let value = value.trim();
+            let unquoted = ['"', '\'']
+                .iter()
+                .find_map(|&q| value.strip_prefix(q)?.strip_suffix(q))
+                .unwrap_or(value);
+            Some((name.trim().to_string(), unquoted.to_string()))
+        })
+        .collect()
+}
+
+/// Variables of the process environment merged with the `.env` file of `dir`, if any.
+/// Variables already set in the environment take precedence, like in most dotenv loaders
+pub fn load_env_variables(dir: &Path) -> Variables {
+    let mut variables = fs::read_to_string(dir.join(".env"))
+        .map(|content| parse_dotenv(&content))
+        .unwrap_or_default();
+    variables.extend(env::vars());
+    variables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_dotenv;
+
+    #[test]
+    fn test_parse_dotenv() {
+        let variables = parse_dotenv(
+            "# api\nBASE_URL=https://api.dev\nexport USER_ID = 42\nTOKEN=\"a b=c\"\nQUOTED='x'\n\nINVALID\n",
+        );
+        assert_eq!(variables.len(), 4);
+        assert_eq!(variables["BASE_URL"], "https://api.dev");
+        assert_eq!(variables["USER_ID"], "42");
+        assert_eq!(variables["TOKEN"], "a b=c");
+        assert_eq!(variables["QUOTED"], "x");
+    }
+}
@@ -0,0 +1,47 @@
+use std::{env, fs, io, process::Command};
+
+/// Opens `text` in `$EDITOR`, falling back to `vi`, and returns the saved content
+pub fn edit(text: &str) -> io::Result<String> {
+    let editor = env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    edit_with(&editor, text)
+}
+
+/// `editor` can include arguments, e.g. `code --wait`
+fn edit_with(editor: &str, text: &str) -> io::Result<String> {
+    let path = env::temp_dir().join(format!("rq-body-{}", std::process::id()));
+    fs::write(&path, text)?;
+
+    let mut args = editor.split_whitespace();
+    let program = args.next().unwrap_or("vi");
+    let ret = match Command::new(program).args(args).arg(&path).status() {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(status) => Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        ))),
+        Err(e) => Err(e),
+    };
+
+    let _ = fs::remove_file(&path);
+    ret
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use super::edit_with;
+
+    #[test]
+    fn test_edit_with() {
+        let editor = std::env::temp_dir().join(format!("rq-editor-{}.sh", std::process::id()));
+        fs::write(&editor, "#!/bin/sh\necho \"new body\" > $1\n").unwrap();
+        fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let edited = edit_with(editor.to_str().unwrap(), "old body");
+        fs::remove_file(&editor).unwrap();
+        assert_eq!(edited.unwrap(), "new body\n");
+
+        assert!(edit_with("false", "old body").is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use rq_core::parser::{parse, HttpRequest};
+
+use std::{fs, io, path::Path};
+
+/// Loads the requests of the favorites file. A missing file has no favorites
+pub fn load_favorites(path: &Path) -> Result<Vec<HttpRequest>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    parse(&content)
+        .map(|file| file.requests)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Appends `req` to the favorites file, creating it and its directory if needed
+pub fn append_favorite(path: &Path, req: &HttpRequest) -> io::Result<()> {
+    let mut content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    if !content.trim().is_empty() {
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str("\n###\n\n");
+    }
+    content.push_str(&req.to_http());
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append_favorite, load_favorites};
+    use rq_core::parser::parse;
+    use std::{env, fs};
+
+    #[test]
+    fn test_append_favorite() {
+        let dir = env::temp_dir().join(format!("rq-favorites-{}", std::process::id()));
+        let path = dir.join("favorites.http");
+        assert!(load_favorites(&path).unwrap().is_empty());
+
+        let requests = parse(
+            "GET https://api.dev/health HTTP/1.1\n\n###\n\nPOST https://auth.dev/token HTTP/1.1\nContent-Type: application/json\n\n{\"user\": \"rq\"}\n",
+        )
+        .unwrap()
+        .requests;
+        for req in &requests {
+            append_favorite(&path, req).unwrap();
+        }
+
+        let favorites = load_favorites(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(favorites.len(), 2);
+        assert_eq!(favorites[0].url, "https://api.dev/health");
+        assert_eq!(favorites[1].url, "https://auth.dev/token");
+        assert_eq!(favorites[1].headers["Content-Type"], "application/json");
+        assert_eq!(favorites[1].body.trim(), "{\"user\": \"rq\"}");
+    }
+}
@@ -0,0 +1,80 @@
+use rq_core::parser::HttpRequest;
+
+use std::str::FromStr;
+
+/// Output format of `--list`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListFormat {
+    /// Aligned columns for humans
+    #[default]
+    Plain,
+    /// Tab separated values for scripts
+    Tsv,
+}
+
+impl FromStr for ListFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "tsv" => Ok(Self::Tsv),
+            _ => Err(format!("unknown list format {}", s)),
+        }
+    }
+}
+
+/// One line per request with its index, method and url
+pub fn format_list(requests: &[HttpRequest], format: ListFormat) -> String {
+    let rows: Vec<(String, String, &str)> = requests
+        .iter()
+        .enumerate()
+        .map(|(i, req)| (i.to_string(), req.method.to_string(), req.url.as_str()))
+        .collect();
+
+    match format {
+        ListFormat::Plain => {
+            let index_width = rows.iter().map(|r| r.0.len()).max().unwrap_or_default();
+            let method_width = rows.iter().map(|r| r.1.len()).max().unwrap_or_default();
+            rows.iter()
+                .map(|(i, method, url)| {
+                    format!("{:>index_width$}  {:<method_width$}  {}\n", i, method, url)
+                })
+                .collect()
+        }
+        ListFormat::Tsv => rows
+            .iter()
+            .map(|(i, method, url)| format!("{}\t{}\t{}\n", i, method, url))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_list, ListFormat};
+    use rq_core::parser::parse;
+
+    #[test]
+    fn test_format_list() {
+        let input = (0..11)
+            .map(|i| {
+                let method = if i == 10 { "DELETE" } else { "GET" };
+                format!("{} https://api.dev/{} HTTP/1.1\n\n", method, i)
+            })
+            .collect::<Vec<_>>()
+            .join("###\n\n");
+        let requests = parse(&input).unwrap().requests;
+
+        let plain = format_list(&requests, ListFormat::Plain);
+        let lines: Vec<&str> = plain.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], " 0  GET     https://api.dev/0");
+        assert_eq!(lines[10], "10  DELETE  https://api.dev/10");
+
+        let tsv = format_list(&requests[9..], ListFormat::Tsv);
+        assert_eq!(
+            tsv,
+            "0\tGET\thttps://api.dev/9\n1\tDELETE\thttps://api.dev/10\n"
+        );
+    }
+}
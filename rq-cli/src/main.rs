@@ -1,27 +1,230 @@
-use rq_core::parser::parse;
+use rq_core::{
+    cookies::{parse_cookie_file, seed_jar, Jar},
+    environment::parse_environments,
+    exchange::ExchangeLog,
+    parser::{
+        is_supported_extension, parse_with_separator, HttpFile, HttpRequest, DEFAULT_SEPARATOR,
+    },
+    request::{Response, Session},
+};
 
 mod app;
+mod args;
+mod browser;
+mod command;
+mod config;
+mod diagnose;
+mod dotenv;
+mod editor;
+mod favorites;
+mod list;
+mod mock;
+mod run;
+mod snippets;
+mod symbols;
 mod terminal;
 
 use app::App;
+use config::Config;
 
 use std::env;
 use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+const ENV_FILE: &str = "http-client.env.json";
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() == 1 {
-        eprintln!("error: no files provided");
+    let args = match args::parse_args(env::args_os()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n{}", e, args::USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    let file_path = args.file_path;
+    let (http_file, replay) = match &args.replay {
+        Some(path) => match load_replay(path) {
+            Ok((requests, responses)) => (
+                HttpFile {
+                    requests,
+                    default_version: None,
+                    variables: Default::default(),
+                },
+                Some(responses),
+            ),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => {
+            let separator = args.separator.as_deref().unwrap_or(DEFAULT_SEPARATOR);
+            let input = read_requests(&file_path)?;
+            (parse_with_separator(&input, separator)?, None)
+        }
+    };
+
+    if args.list {
+        print!(
+            "{}",
+            list::format_list(&http_file.requests, args.list_format)
+        );
+        return Ok(());
+    }
+
+    let config = Config::load()?;
+    let tick_rate = Duration::from_millis(args.tick_rate.unwrap_or(config.tick_rate_ms));
+    let snippets = Config::snippets_dir()
+        .map(|dir| snippets::load_snippets(&dir))
+        .unwrap_or_default();
+    // Environments are defined next to the requests file
+    let env_path = Path::new(&file_path).with_file_name(ENV_FILE);
+    let environments = match fs::read_to_string(env_path) {
+        Ok(json) => parse_environments(&json)?,
+        Err(_) => Default::default(),
+    };
+    if let Some(env) = args
+        .env
+        .as_ref()
+        .filter(|env| !environments.contains_key(*env))
+    {
+        eprintln!("error: unknown environment '{}'", env);
         std::process::exit(1);
     }
 
-    let file_path = args[1].to_string();
-    let file_content = fs::read_to_string(&file_path)?;
-    let http_file = parse(&file_content)?;
+    let env_variables =
+        dotenv::load_env_variables(Path::new(&file_path).parent().unwrap_or(Path::new("")));
+
+    let mut client_config = config.client_config();
+    client_config.base_dir = Path::new(&file_path)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .to_path_buf();
+    client_config.timeout = args.timeout;
+    if let Some(path) = &args.cookie_jar {
+        client_config.cookie_jar = Some(load_cookie_jar(path)?);
+    }
+    // Cookies set by the responses are sent with the next requests of the session
+    let session = match Session::new(client_config) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if args.run.is_some() || args.headless {
+        let indices = match &args.run {
+            Some(spec) => match run::parse_indices(spec, http_file.requests.len()) {
+                Ok(indices) => indices,
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            None => (0..http_file.requests.len()).collect(),
+        };
+        let output_dir = args.output_dir.as_deref().map(Path::new);
+        if let Some(dir) = output_dir {
+            fs::create_dir_all(dir)?;
+        }
+        let environment = args.env.as_ref().and_then(|env| environments.get(env));
+        let base_dir = Path::new(&file_path).parent().unwrap_or(Path::new(""));
+        let summary = run::run(
+            &http_file.requests,
+            &indices,
+            environment,
+            &env_variables,
+            base_dir,
+            &session,
+            &run::RunOptions {
+                trace_vars: args.trace_vars,
+                output_dir,
+                line_ending: config.save_line_endings,
+            },
+        )
+        .await;
+        std::process::exit(if summary.success() { 0 } else { 1 });
+    }
+
+    if let Some(addr) = &args.serve {
+        let environment = args.env.as_ref().and_then(|env| environments.get(env));
+        let server =
+            mock::MockServer::record(&http_file.requests, environment, session.config()).await;
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        eprintln!(
+            "serving recorded responses at http://{}",
+            listener.local_addr()?
+        );
+        server.serve(listener).await?;
+        return Ok(());
+    }
 
-    let app = App::new(file_path, http_file);
-    terminal::start(app).await?;
+    let mut app = App::new(file_path, http_file, &config, session, snippets);
+    app.environments = environments;
+    app.environment = args.env;
+    app.env_variables = env_variables;
+    app.replay = replay;
+    app.favorites_path = Config::favorites_path();
+    if let Some(path) = &app.favorites_path {
+        app.favorites = favorites::load_favorites(path).unwrap_or_else(|e| {
+            eprintln!("warning: {}", e);
+            Vec::new()
+        });
+    }
+    app.exchange_log = args.log.map(|path| (path, ExchangeLog::default()));
+    app.symbols = if args.ascii {
+        &symbols::ASCII
+    } else {
+        symbols::detect()
+    };
+    terminal::start(app, tick_rate, args.idle).await?;
 
     std::process::exit(0)
 }
+
+/// Reads the requests file, or stdin when `file_path` is `-`
+fn read_requests(file_path: &str) -> io::Result<String> {
+    if file_path == "-" {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        return Ok(content);
+    }
+    if !is_supported_extension(Path::new(file_path)) {
+        eprintln!("warning: {} is not a .http, .rest or .rq file", file_path);
+    }
+    fs::read_to_string(file_path)
+}
+
+/// Loads the requests and responses of an exchange log written with `--log`
+fn load_replay(
+    path: &str,
+) -> Result<(Vec<HttpRequest>, Vec<Response>), Box<dyn std::error::Error>> {
+    let log = ExchangeLog::parse(&fs::read_to_string(path)?)?;
+    let mut requests = Vec::new();
+    let mut responses = Vec::new();
+    for exchange in &log.exchanges {
+        requests.push(exchange.request()?);
+        responses.push(exchange.response()?);
+    }
+    if requests.is_empty() {
+        return Err(format!("{} has no exchanges", path).into());
+    }
+    Ok((requests, responses))
+}
+
+/// Seeds a cookie jar with the cookies of `path`, warning about malformed lines
+fn load_cookie_jar(path: &str) -> io::Result<Arc<Jar>> {
+    let (cookies, warnings) = parse_cookie_file(&fs::read_to_string(path)?);
+    for warning in warnings {
+        eprintln!("warning: {}: {}", path, warning);
+    }
+    let jar = Jar::default();
+    seed_jar(&jar, &cookies);
+    Ok(Arc::new(jar))
+}
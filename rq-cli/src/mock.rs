@@ -0,0 +1,207 @@
+use rq_core::{
+    environment::Environment,
+    parser::HttpRequest,
+    request::{execute, ClientConfig, Response},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use std::{io, sync::Arc};
+
+/// Response recorded for the requests with the same method and path
+struct Recording {
+    method: String,
+    path: String,
+    response: Response,
+}
+
+/// Serves the recorded responses of a requests file, see `--serve`
+#[derive(Default)]
+pub struct MockServer {
+    recordings: Vec<Recording>,
+}
+
+impl MockServer {
+    /// Sends every request once and records its response.
+    /// Failed requests are reported to stderr and not served.
+    pub async fn record(
+        requests: &[HttpRequest],
+        environment: Option<&Environment>,
+        client_config: &ClientConfig,
+    ) -> Self {
+        let mut server = Self::default();
+        for req in requests {
+            let req = match environment {
+                Some(env) => env.resolve(req),
+                None => req.clone(),
+            };
+            match execute(&req, client_config).await {
+                Ok(response) => server.insert(&req, response),
+                Err(e) => eprintln!("error: {} {}: {}", req.method, req.url, e),
+            }
+        }
+        server
+    }
+
+    fn insert(&mut self, req: &HttpRequest, response: Response) {
+        self.recordings.push(Recording {
+            method: req.method.to_string(),
+            path: url_path(&req.url).to_string(),
+            response,
+        });
+    }
+
+    /// First recorded response for `method` and `path`, the query is ignored
+    fn find(&self, method: &str, path: &str) -> Option<&Response> {
+        let path = url_path(path);
+        self.recordings
+            .iter()
+            .find(|r| r.method.eq_ignore_ascii_case(method) && r.path == path)
+            .map(|r| &r.response)
+    }
+
+    /// Accepts connections until the listener fails, answering unknown requests with `404`
+    pub async fn serve(self, listener: TcpListener) -> io::Result<()> {
+        let server = Arc::new(self);
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let server = Arc::clone(&server);
+            tokio::spawn(async move {
+                if let Err(e) = server.handle(stream).await {
+                    eprintln!("error: {}", e);
+                }
+            });
+        }
+    }
+
+    async fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut head = Vec::new();
+        let mut buf = [0; 1024];
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            head.extend_from_slice(&buf[..n]);
+        }
+
+        let head = String::from_utf8_lossy(&head);
+        let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+        let method = request_line.next().unwrap_or_default();
+        let path = request_line.next().unwrap_or_default();
+        let response = match self.find(method, path) {
+            Some(response) => format_response(response),
+            None => {
+                b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec()
+            }
+        };
+        stream.write_all(&response).await?;
+        stream.shutdown().await
+    }
+}
+
+/// Path of a url or request target, without the query and fragment
+fn url_path(url: &str) -> &str {
+    let url = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest.find('/').map_or("/", |i| &rest[i..]));
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    match &url[..end] {
+        "" => "/",
+        path => path,
+    }
+}
+
+/// Response as sent on the wire. The body is already decompressed so the framing
+/// headers are replaced
+fn format_response(response: &Response) -> Vec<u8> {
+    let mut out = format!("HTTP/1.1 {}\r\n", response.status_line());
+    for (name, value) in response.headers.iter() {
+        if matches!(
+            name.as_str(),
+            "content-length" | "transfer-encoding" | "content-encoding" | "connection"
+        ) {
+            continue;
+        }
+        out.push_str(&format!(
+            "{}: {}\r\n",
+            name,
+            String::from_utf8_lossy(value.as_bytes())
+        ));
+    }
+    out.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.bytes.len()
+    ));
+
+    let mut out = out.into_bytes();
+    out.extend_from_slice(&response.bytes);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{url_path, MockServer};
+    use rq_core::{
+        parser::parse,
+        request::{execute, ClientConfig, Response},
+    };
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_url_path() {
+        assert_eq!(url_path("https://api.dev/users?page=2"), "/users");
+        assert_eq!(url_path("https://api.dev"), "/");
+        assert_eq!(url_path("/users/1#top"), "/users/1");
+    }
+
+    #[tokio::test]
+    async fn test_serve_recorded_response() {
+        let recorded = parse(
+            "GET https://api.dev/users?page=1 HTTP/1.1\n\n###\n\nPOST https://api.dev/users HTTP/1.1\n\n",
+        )
+        .unwrap()
+        .requests;
+        let mut server = MockServer::default();
+        server.insert(
+            &recorded[0],
+            Response {
+                status: 200.try_into().unwrap(),
+                headers: [(
+                    "content-type".parse().unwrap(),
+                    "application/json".parse().unwrap(),
+                )]
+                .into_iter()
+                .collect(),
+                body: "[\"alice\"]".into(),
+                bytes: "[\"alice\"]".into(),
+                ..Default::default()
+            },
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(server.serve(listener));
+
+        let input = format!(
+            "GET http://{0}/users HTTP/1.1\n\n###\n\nPOST http://{0}/users HTTP/1.1\n\n",
+            addr
+        );
+        let requests = parse(&input).unwrap().requests;
+
+        let res = execute(&requests[0], &ClientConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(res.status, 200);
+        assert_eq!(res.content_type(), Some("application/json"));
+        assert_eq!(res.body, "[\"alice\"]");
+
+        // Only the GET was recorded
+        let res = execute(&requests[1], &ClientConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(res.status, 404);
+    }
+}
@@ -0,0 +1,365 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    path::Path,
+};
+
+use rq_core::{
+    assertions::{self, Summary},
+    encoding::LineEnding,
+    environment::Environment,
+    parser::HttpRequest,
+    request::{Response, Session},
+    variables::{capture_headers, resolve, Resolution, Variables},
+};
+
+use crate::app::save_body;
+
+/// How [`run`] reports the requests it sends
+#[derive(Debug, Default)]
+pub struct RunOptions<'a> {
+    /// Print the resolution of the placeholders of each request to stderr
+    pub trace_vars: bool,
+    /// Directory each response body is saved to, in a file named after the request index
+    pub output_dir: Option<&'a Path>,
+    /// Line endings of the saved bodies
+    pub line_ending: LineEnding,
+}
+
+/// Parses a `--run` spec such as `1,3,5` or `2-4` into the indices of the requests to send, in order.
+/// Indices are the ones printed by `--list`.
+pub fn parse_indices(spec: &str, count: usize) -> Result<Vec<usize>, String> {
+    let index = |s: &str| -> Result<usize, String> {
+        let i = s
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid request index '{}'", s.trim()))?;
+        if i >= count {
+            return Err(format!(
+                "request index {} out of range, the file has {} requests",
+                i, count
+            ));
+        }
+        Ok(i)
+    };
+
+    let mut indices = Vec::new();
+    for part in spec.split(',') {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (index(start)?, index(end)?);
+                if start > end {
+                    return Err(format!("invalid request range '{}'", part.trim()));
+                }
+                indices.extend(start..=end);
+            }
+            None => indices.push(index(part)?),
+        }
+    }
+    Ok(indices)
+}
+
+/// Sends the requests at `indices` one after the other, printing each response and then
+/// the tally to stderr. A request passes without error, failed `# @expect-status` and,
+/// for requests without assertions, 4xx or 5xx status.
+/// Placeholders are replaced with the headers captured by the previous requests
+/// (`# @capture-header`) and then `env_variables`, and requests with undefined ones fail.
+/// Body helper files are relative to `base_dir`.
+pub async fn run(
+    requests: &[HttpRequest],
+    indices: &[usize],
+    environment: Option<&Environment>,
+    env_variables: &Variables,
+    base_dir: &Path,
+    session: &Session,
+    options: &RunOptions<'_>,
+) -> Summary {
+    let mut results = Vec::new();
+    let mut captured = Variables::new();
+    for (n, &i) in indices.iter().enumerate() {
+        let req = match environment {
+            Some(env) => env.resolve(&requests[i]),
+            None => requests[i].clone(),
+        };
+        // A single response is printed alone, e.g. to be piped
+        if n > 0 {
+            println!();
+        }
+        if indices.len() > 1 {
+            println!("### {} {} {}", i, req.method, req.url);
+        }
+        if options.trace_vars {
+            eprint!(
+                "{}",
+                format_trace(&req.trace_variables(&captured, env_variables, base_dir))
+            );
+        }
+        // Captured values take precedence, like in the TUI
+        let req = match resolve(&req.with_variables(&captured), env_variables) {
+            Ok(req) => req,
+            Err(names) => {
+                eprintln!("error: undefined variables: {}", names.join(", "));
+                results.push(Some(false));
+                continue;
+            }
+        };
+        let req = match req.with_helpers(base_dir) {
+            Ok(req) => req,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                results.push(Some(false));
+                continue;
+            }
+        };
+        let passed = match session.execute(&req).await {
+            Ok(response) => {
+                for header in capture_headers(&req, &response, &mut captured) {
+                    eprintln!("warning: header {} not found for capture", header);
+                }
+                let mut passed = assertions::check(&req, &response).unwrap_or(
+                    !response.status.is_client_error() && !response.status.is_server_error(),
+                );
+                if let Err(e) = print_response(&response) {
+                    eprintln!("error: {}", e);
+                    passed = false;
+                }
+                if let Some(dir) = options.output_dir {
+                    let path = dir.join(output_file_name(i, &response));
+                    let path = path.to_string_lossy();
+                    if let Err(e) = save_body(&response, &path, "utf-8", options.line_ending) {
+                        eprintln!("error: {}: {}", path, e);
+                        passed = false;
+                    }
+                }
+                passed
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                false
+            }
+        };
+        results.push(Some(passed));
+    }
+
+    // On stderr so that a single response can be piped
+    let summary = Summary::from_results(&results);
+    eprintln!("{}", summary);
+    summary
+}
+
+/// Prints `response` to stdout. When stdout is redirected, e.g. to a file, binary bodies are
+/// written as received and the status line and headers go to stderr. On a terminal they are
+/// replaced by their size
+fn print_response(response: &Response) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    if response.binary && !stdout.is_terminal() {
+        eprint!("{}", response.head());
+        stdout.write_all(&response.bytes)
+    } else {
+        writeln!(stdout, "{}", response)
+    }
+}
+
+/// Name of the file the response of the request at `index` is saved to, e.g. `3.json`
+fn output_file_name(index: usize, response: &Response) -> String {
+    let content_type = response.content_type().unwrap_or_default();
+    let extension = if response.binary {
+        "bin"
+    } else if response.cbor || content_type.contains("json") {
+        "json"
+    } else if content_type.contains("html") {
+        "html"
+    } else if content_type.contains("xml") {
+        "xml"
+    } else {
+        "txt"
+    };
+    format!("{}.{}", index, extension)
+}
+
+/// One `{{placeholder}} = value (source)` line per placeholder, with secrets masked
+pub fn format_trace(trace: &[Resolution]) -> String {
+    let width = trace
+        .iter()
+        .map(|r| r.placeholder.chars().count())
+        .max()
+        .unwrap_or_default();
+    trace
+        .iter()
+        .map(|r| {
+            format!(
+                "{{{{{}}}}}{:pad$} = {} ({})\n",
+                r.placeholder,
+                "",
+                r.display_value(),
+                r.source,
+                pad = width - r.placeholder.chars().count()
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_trace, output_file_name, parse_indices, run, RunOptions};
+    use rq_core::{
+        assertions::Summary,
+        parser::parse,
+        request::{Response, Session},
+        variables::{Resolution, VariableSource, Variables},
+    };
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        path::Path,
+        sync::mpsc,
+        thread,
+    };
+
+    /// Answers one connection per response, in order, and sends back the raw requests
+    fn serve(responses: &[&str]) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let responses: Vec<_> = responses.iter().map(|r| r.to_string()).collect();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 4096];
+                let n = stream.read(&mut buf).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+                tx.send(String::from_utf8_lossy(&buf[..n]).to_string())
+                    .unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_run_summary() {
+        let (url, _rx) = serve(&[
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        let file = parse(&format!(
+            "GET {url}/ok HTTP/1.1\n\n###\n\nGET {url}/missing HTTP/1.1\n\n###\n\n\
+             # @expect-status 404\nGET {url}/missing HTTP/1.1\n\n###\n\n\
+             GET http://127.0.0.1:1/ HTTP/1.1\n\n",
+            url = url
+        ))
+        .unwrap();
+
+        let summary = run(
+            &file.requests,
+            &[0, 1, 2, 3],
+            None,
+            &Variables::new(),
+            Path::new(""),
+            &Session::default(),
+            &RunOptions::default(),
+        )
+        .await;
+        assert_eq!(
+            summary,
+            Summary {
+                requests: 4,
+                passed: 2,
+                failed: 2
+            }
+        );
+        assert!(!summary.success());
+    }
+
+    #[tokio::test]
+    async fn test_run_captures_headers() {
+        let (url, rx) = serve(&[
+            "HTTP/1.1 200 OK\r\nX-Token: abc\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        let file = parse(&format!(
+            "# @capture-header token X-Token\nPOST {url}/login HTTP/1.1\n\n###\n\n\
+             GET {url}/me HTTP/1.1\nAuthorization: Bearer {{{{token}}}}\n\n",
+            url = url
+        ))
+        .unwrap();
+        // The captured value wins over the environment
+        let env_variables = Variables::from([("token".to_string(), "env".to_string())]);
+
+        let summary = run(
+            &file.requests,
+            &[0, 1],
+            None,
+            &env_variables,
+            Path::new(""),
+            &Session::default(),
+            &RunOptions::default(),
+        )
+        .await;
+        assert!(summary.success());
+        let _login = rx.recv().unwrap();
+        let me = rx.recv().unwrap().to_lowercase();
+        assert!(me.contains("authorization: bearer abc"), "{}", me);
+    }
+
+    #[test]
+    fn test_output_file_name() {
+        let response = |content_type: &str, binary| Response {
+            headers: [("content-type", content_type)]
+                .into_iter()
+                .map(|(k, v)| (k.parse().unwrap(), v.parse().unwrap()))
+                .collect(),
+            binary,
+            ..Default::default()
+        };
+        assert_eq!(
+            output_file_name(0, &response("application/json", false)),
+            "0.json"
+        );
+        assert_eq!(
+            output_file_name(3, &response("text/html; charset=utf-8", false)),
+            "3.html"
+        );
+        assert_eq!(output_file_name(4, &response("text/plain", false)), "4.txt");
+        assert_eq!(output_file_name(5, &response("image/png", true)), "5.bin");
+    }
+
+    #[test]
+    fn test_format_trace() {
+        let trace = [
+            Resolution {
+                placeholder: "host".to_string(),
+                value: Ok("api.dev".to_string()),
+                source: VariableSource::Capture,
+            },
+            Resolution {
+                placeholder: "api_token".to_string(),
+                value: Ok("s3cr3t".to_string()),
+                source: VariableSource::Capture,
+            },
+            Resolution {
+                placeholder: "id".to_string(),
+                value: Err("unresolved".to_string()),
+                source: VariableSource::Unresolved,
+            },
+        ];
+        assert_eq!(
+            format_trace(&trace),
+            "{{host}}      = api.dev (capture)\n{{api_token}} = ****** (capture)\n{{id}}        = <unresolved> (unresolved)\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_indices() {
+        assert_eq!(parse_indices("3", 6).unwrap(), [3]);
+        assert_eq!(parse_indices("1,3,5", 6).unwrap(), [1, 3, 5]);
+        assert_eq!(parse_indices("2-4", 6).unwrap(), [2, 3, 4]);
+        assert_eq!(parse_indices("5, 0-1,3", 6).unwrap(), [5, 0, 1, 3]);
+
+        assert!(parse_indices("6", 6).is_err());
+        assert!(parse_indices("1,9", 6).is_err());
+        assert!(parse_indices("4-8", 6).is_err());
+        assert!(parse_indices("4-2", 6).is_err());
+        assert!(parse_indices("a", 6).is_err());
+        assert!(parse_indices("1,", 6).is_err());
+    }
+}
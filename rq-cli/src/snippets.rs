@@ -0,0 +1,69 @@
+use rq_core::parser::{is_supported_extension, parse, HttpRequest};
+
+use std::{fs, path::Path};
+
+/// Request template loaded from a request file fragment
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    pub name: String,
+    pub requests: Vec<HttpRequest>,
+}
+
+/// Loads all the request files from `dir`. Files that can't be read or parsed are skipped.
+pub fn load_snippets(dir: &Path) -> Vec<Snippet> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut snippets: Vec<Snippet> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_supported_extension(path))
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            let file = parse(&content).ok()?;
+            Some(Snippet {
+                name: path.file_stem()?.to_string_lossy().to_string(),
+                requests: file.requests,
+            })
+        })
+        .collect();
+    snippets.sort_by(|a, b| a.name.cmp(&b.name));
+    snippets
+}
+
+/// Inserts the snippet requests right after `index`, returning the index of the first inserted one
+pub fn insert_snippet(requests: &mut Vec<HttpRequest>, index: usize, snippet: &Snippet) -> usize {
+    let at = (index + 1).min(requests.len());
+    requests.splice(at..at, snippet.requests.iter().cloned());
+    at
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{insert_snippet, Snippet};
+    use rq_core::parser::parse;
+
+    #[test]
+    fn test_insert_snippet() {
+        let mut requests = parse("GET a.dev HTTP/1.1\n\n###\n\nGET b.dev HTTP/1.1\n\n")
+            .unwrap()
+            .requests;
+        let snippet = Snippet {
+            name: "json-post".to_string(),
+            requests: parse("POST {{host}}/items HTTP/1.1\ncontent-type: application/json\n\n{}")
+                .unwrap()
+                .requests,
+        };
+
+        let at = insert_snippet(&mut requests, 0, &snippet);
+        assert_eq!(at, 1);
+        let urls: Vec<&str> = requests.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, vec!["a.dev", "{{host}}/items", "b.dev"]);
+        assert_eq!(
+            requests[1].to_string(),
+            "POST {{host}}/items HTTP/1.1 [content-type: application/json]"
+        );
+        assert_eq!(requests[1].body, "{}");
+    }
+}
@@ -0,0 +1,155 @@
+use rq_core::parser::HttpMethod;
+
+use std::env;
+
+use tui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+/// Glyphs used to draw the UI
+#[derive(Debug, PartialEq, Eq)]
+pub struct Symbols {
+    /// Prefix of the selected list item
+    pub highlight: &'static str,
+    pub spinner: &'static [&'static str],
+    /// Prefix of the requests whose last send failed
+    pub failed: &'static str,
+    /// Whether box-drawing borders are replaced by [`AsciiBorders`]
+    pub ascii_borders: bool,
+}
+
+pub const UNICODE: Symbols = Symbols {
+    highlight: "▶ ",
+    spinner: &["◐", "◓", "◑", "◒"],
+    failed: "✗",
+    ascii_borders: false,
+};
+
+pub const ASCII: Symbols = Symbols {
+    highlight: "> ",
+    spinner: &["|", "/", "-", "\\"],
+    failed: "x",
+    ascii_borders: true,
+};
+
+/// Picks the symbols supported by the terminal, based on `TERM` and the locale
+pub fn detect() -> &'static Symbols {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|key| env::var(key).ok().filter(|v| !v.is_empty()));
+    if supports_unicode(env::var("TERM").ok().as_deref(), locale.as_deref()) {
+        &UNICODE
+    } else {
+        &ASCII
+    }
+}
+
+fn supports_unicode(term: Option<&str>, locale: Option<&str>) -> bool {
+    // The Linux console and dumb terminals lack most of the glyphs
+    if matches!(term, Some("linux" | "dumb")) {
+        return false;
+    }
+    locale.is_some_and(|locale| {
+        let locale = locale.to_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    })
+}
+
+/// Nerd Font icon of the request method, empty when `nerd` is disabled
+pub fn method_icon(method: &HttpMethod, nerd: bool) -> &'static str {
+    if !nerd {
+        return "";
+    }
+    match method {
+        HttpMethod::Get => "\u{f01da}",
+        HttpMethod::Post => "\u{f0552}",
+        HttpMethod::Put => "\u{f03eb}",
+        HttpMethod::Delete => "\u{f01b4}",
+        HttpMethod::Patch => "\u{f0cb6}",
+        HttpMethod::Head => "\u{f02fc}",
+        HttpMethod::Options => "\u{f0493}",
+        HttpMethod::Connect => "\u{f0337}",
+    }
+}
+
+/// Replaces the box-drawing characters already in the buffer with ASCII ones.
+/// Rendered last, over the whole frame.
+pub struct AsciiBorders;
+
+impl Widget for AsciiBorders {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                if let Some(glyph) = ascii_glyph(&cell.symbol) {
+                    cell.set_symbol(glyph);
+                }
+            }
+        }
+    }
+}
+
+fn ascii_glyph(symbol: &str) -> Option<&'static str> {
+    match symbol {
+        "─" | "━" | "═" => Some("-"),
+        "│" | "┃" | "║" => Some("|"),
+        "┌" | "┐" | "└" | "┘" | "╭" | "╮" | "╰" | "╯" | "┏" | "┓" | "┗" | "┛" | "╔" | "╗" | "╚"
+        | "╝" => Some("+"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::{
+        backend::TestBackend,
+        buffer::Buffer,
+        widgets::{Block, Borders},
+        Terminal,
+    };
+
+    use rq_core::parser::HttpMethod;
+
+    use super::{method_icon, supports_unicode, AsciiBorders, ASCII, UNICODE};
+
+    #[test]
+    fn test_supports_unicode() {
+        assert!(supports_unicode(
+            Some("xterm-256color"),
+            Some("en_US.UTF-8")
+        ));
+        assert!(supports_unicode(None, Some("C.utf8")));
+        assert!(!supports_unicode(Some("xterm"), Some("C")));
+        assert!(!supports_unicode(Some("xterm"), None));
+        assert!(!supports_unicode(Some("linux"), Some("en_US.UTF-8")));
+    }
+
+    #[test]
+    fn test_ascii_theme() {
+        assert!(ASCII.highlight.is_ascii());
+        assert!(ASCII.spinner.iter().all(|frame| frame.is_ascii()));
+        assert!(ASCII.failed.is_ascii());
+        assert!(!UNICODE.highlight.is_ascii());
+
+        let mut terminal = Terminal::new(TestBackend::new(4, 3)).unwrap();
+        terminal
+            .draw(|f| {
+                f.render_widget(Block::default().borders(Borders::ALL), f.size());
+                f.render_widget(AsciiBorders, f.size());
+            })
+            .unwrap();
+        terminal
+            .backend()
+            .assert_buffer(&Buffer::with_lines(vec!["+--+", "|  |", "+--+"]));
+    }
+
+    #[test]
+    fn test_method_icon() {
+        for method in HttpMethod::iterator() {
+            assert_eq!(method_icon(method, false), "");
+            assert!(!method_icon(method, true).is_empty());
+        }
+        assert_ne!(
+            method_icon(&HttpMethod::Get, true),
+            method_icon(&HttpMethod::Post, true)
+        );
+    }
+}
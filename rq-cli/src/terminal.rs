@@ -9,20 +9,34 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use rq_core::parser::HttpRequest;
+use rq_core::{
+    diff::DiffChunk,
+    format::{indent_json, DEFAULT_INDENT},
+    parser::HttpRequest,
+    variables::VariableSource,
+};
 
 use tui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame, Terminal,
 };
 
-use crate::app::App;
+use crate::{
+    app::{match_ranges, request_line, App, Focus, Follow, Prompt, SessionStats, VimMode},
+    diagnose::{summarize, Diagnosis, RUNS},
+    editor,
+    symbols::{method_icon, AsciiBorders},
+};
 
-pub async fn start(app: App) -> Result<(), Box<dyn Error>> {
+pub async fn start(
+    app: App,
+    tick_rate: Duration,
+    idle: Option<Duration>,
+) -> Result<(), Box<dyn Error>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -30,8 +44,7 @@ pub async fn start(app: App) -> Result<(), Box<dyn Error>> {
     let mut terminal = Terminal::new(backend)?;
     terminal.clear().unwrap();
 
-    let tick_rate = Duration::from_millis(250);
-    let res = run_app(&mut terminal, app, tick_rate).await;
+    let res = run_app(&mut terminal, app, tick_rate, idle).await;
 
     // restore terminal
     disable_raw_mode()?;
@@ -42,19 +55,27 @@ pub async fn start(app: App) -> Result<(), Box<dyn Error>> {
     )?;
     terminal.show_cursor()?;
 
-    if let Err(err) = res {
-        println!("{:?}", err)
+    match res {
+        Ok(true) => println!(
+            "Exited after {}s without input",
+            idle.unwrap_or_default().as_secs()
+        ),
+        Ok(false) => {}
+        Err(err) => println!("{:?}", err),
     }
 
     Ok(())
 }
 
+/// Runs the UI until the user exits. Returns whether it exited because of the `idle` timeout
 async fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     mut app: App,
     tick_rate: Duration,
-) -> Result<(), Box<dyn std::error::Error>> {
+    idle: Option<Duration>,
+) -> Result<bool, Box<dyn std::error::Error>> {
     let mut last_tick = Instant::now();
+    let mut last_input = Instant::now();
 
     loop {
         app.tick();
@@ -64,28 +85,153 @@ async fn run_app<B: Backend>(
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
         if crossterm::event::poll(timeout)? {
+            last_input = Instant::now();
             app.on_terminal_event(event::read()?).await?;
             if app.exited {
-                return Ok(());
+                return Ok(false);
             }
+            if let Some(index) = app.edit_request.take() {
+                let body = app.request_body(index);
+                let edited = suspend(terminal, || editor::edit(&body))?;
+                app.set_body_override(index, edited);
+            }
+        }
+        if idle_expired(last_input, Instant::now(), idle) {
+            app.save_config();
+            return Ok(true);
         }
         if last_tick.elapsed() >= tick_rate {
+            app.save_config();
             last_tick = Instant::now();
         }
     }
 }
 
+/// Whether no input arrived for the `idle` timeout, if any
+fn idle_expired(last_input: Instant, now: Instant, idle: Option<Duration>) -> bool {
+    idle.is_some_and(|idle| now.saturating_duration_since(last_input) >= idle)
+}
+
+/// Restores the terminal while `f` runs, e.g. to open an external editor
+fn suspend<B: Backend, T>(
+    terminal: &mut Terminal<B>,
+    f: impl FnOnce() -> T,
+) -> Result<T, Box<dyn Error>> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+
+    let ret = f();
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+    Ok(ret)
+}
+
+/// Below this width only one pane is shown at a time
+const NARROW_WIDTH: u16 = 60;
+
+#[derive(Debug, PartialEq, Eq)]
+enum LayoutMode {
+    /// Requests and response side by side
+    Horizontal,
+    /// Requests stacked on top of the response
+    Vertical,
+    /// Only one pane visible, toggled with `Tab`
+    Single,
+}
+
+fn layout_mode(area: Rect) -> LayoutMode {
+    if area.width < NARROW_WIDTH {
+        LayoutMode::Single
+    } else if area.width >= area.height {
+        LayoutMode::Horizontal
+    } else {
+        LayoutMode::Vertical
+    }
+}
+
 fn draw_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
-    // Create two chunks with equal horizontal screen space
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
         .split(f.size());
+    let mut area = rows[0];
+    match app.prompt.as_ref() {
+        // The command line replaces the status bar, vim style
+        Some((Prompt::Command, value)) => {
+            f.render_widget(Paragraph::new(format!(":{}", value)), rows[1])
+        }
+        _ => f.render_widget(draw_status_bar(app, rows[1]), rows[1]),
+    }
+
+    if let Some(query) = app.search.as_deref() {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(area);
+        area = rows[0];
+        f.render_widget(draw_search_bar(query, app.match_indices.len()), rows[1]);
+    }
+
+    let split = [
+        Constraint::Percentage(app.list_percent),
+        Constraint::Percentage(100 - app.list_percent),
+    ];
+    let chunks = match layout_mode(area) {
+        LayoutMode::Horizontal => Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(split)
+            .split(area),
+        LayoutMode::Vertical => Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(split)
+            .split(area),
+        // The hidden pane gets an empty area
+        LayoutMode::Single if app.focus == Focus::Response => vec![Rect::default(), area],
+        LayoutMode::Single => vec![area, Rect::default()],
+    };
+
+    if let Some(position) = app.click_position.take() {
+        if contains(chunks[0], position) {
+            app.focus = Focus::Requests;
+        } else if contains(chunks[1], position) {
+            app.focus = Focus::Response;
+        }
+    }
 
     let request_spans: Vec<ListItem> = app
         .requests
         .iter()
-        .map(|i| ListItem::new(draw_request(i)))
+        .enumerate()
+        .map(|(i, req)| {
+            let matched = app.match_positions.get(&i).map_or(&[][..], Vec::as_slice);
+            let mut lines =
+                draw_request(req, app.nerd_font_icons, app.pretty_request_body, matched);
+            if let Some(since) = app.pending_since(i) {
+                lines[0].0.insert(
+                    0,
+                    Span::raw(format!(
+                        "{} ",
+                        spinner_frame(app.symbols.spinner, since.elapsed())
+                    )),
+                );
+            } else if app.request_failed(i) {
+                lines[0].0.insert(
+                    0,
+                    Span::styled(
+                        format!("{} ", app.symbols.failed),
+                        Style::default().fg(Color::Red),
+                    ),
+                );
+            }
+            let item = ListItem::new(lines);
+            if app.match_indices.contains(&i) {
+                item.style(Style::default().bg(Color::DarkGray))
+            } else {
+                item
+            }
+        })
         .collect();
 
     let mut list_block = Block::default()
@@ -97,28 +243,786 @@ fn draw_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                 .add_modifier(Modifier::BOLD)
                 .fg(Color::Green),
         )
-        .highlight_symbol("> ");
+        .highlight_symbol(app.symbols.highlight);
 
-    let cursor_x = app.cursor_position.0;
-    if chunks[0].x <= cursor_x && cursor_x < chunks[0].x + chunks[0].width {
+    if app.focus == Focus::Requests {
         list_block = list_block.border_style(Style::default().fg(Color::Blue));
     }
 
-    let mut buffer_block = Block::default().borders(Borders::ALL);
-    if chunks[1].x <= cursor_x && cursor_x < chunks[1].x + chunks[1].width {
+    let mut buffer_block = Block::default()
+        .borders(Borders::ALL)
+        .title(match &app.header_filter {
+            Some(filter) => format!(
+                "{} [headers: {}]",
+                response_title(app.response_request.as_ref()),
+                filter
+            ),
+            None => response_title(app.response_request.as_ref()),
+        });
+    if app.focus == Focus::Response {
         buffer_block = buffer_block.border_style(Style::default().fg(Color::Blue));
     }
-    let buffer = Paragraph::new(app.response_buffer.as_str()).wrap(Wrap { trim: true });
+    // Inside the borders
+    app.response_height = chunks[1].height.saturating_sub(2);
+    app.response_scroll = app
+        .follow
+        .scroll(app.response_scroll, app.response_bottom());
+    let buffer = if app.response_buffer.is_empty() {
+        Paragraph::new(draw_empty_response(&app.unresolved_variables()))
+    } else {
+        let tint = app
+            .response
+            .as_ref()
+            .filter(|_| app.tint_response_body)
+            .and_then(|response| body_tint(response.status.as_u16()));
+        draw_response(
+            &app.response_buffer,
+            app.trim_response(),
+            app.response_scroll,
+            app.visual_selection(),
+            tint,
+            app.json_body,
+            app.response_query.as_deref(),
+        )
+    };
 
     f.render_stateful_widget(list.block(list_block), chunks[0], &mut app.list);
     f.render_widget(buffer.block(buffer_block), chunks[1]);
+
+    if let Some(picker) = app.snippet_picker.as_mut() {
+        let items: Vec<ListItem> = app
+            .snippets
+            .iter()
+            .map(|s| ListItem::new(s.name.as_str()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Snippets"))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(app.symbols.highlight);
+        let popup = popup_area(f.size(), 40, 25, 30, 8);
+        f.render_widget(Clear, popup);
+        f.render_stateful_widget(list, popup, picker);
+    }
+
+    if let Some(picker) = app.favorite_picker.as_mut() {
+        let items: Vec<ListItem> = app
+            .favorites
+            .iter()
+            .map(|req| ListItem::new(request_line(req)))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Favorites"))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(app.symbols.highlight);
+        let popup = popup_area(f.size(), 60, 40, 40, 8);
+        f.render_widget(Clear, popup);
+        f.render_stateful_widget(list, popup, picker);
+    }
+
+    if let Some((prompt, value)) = app
+        .prompt
+        .as_ref()
+        .filter(|(prompt, _)| *prompt != Prompt::Command)
+    {
+        // Searches without matches are tinted red
+        let style = match prompt {
+            Prompt::ResponseSearch if !value.is_empty() && app.response_matches.is_empty() => {
+                Style::default().fg(Color::Red)
+            }
+            _ => Style::default(),
+        };
+        let input = Paragraph::new(value.as_str())
+            .style(style)
+            .block(Block::default().borders(Borders::ALL).title(prompt.title()));
+        let popup = popup_area(f.size(), 40, 0, 30, 3);
+        f.render_widget(Clear, popup);
+        f.render_widget(input, popup);
+    }
+
+    if app.variables_open {
+        let mut variables: Vec<_> = app.variables.iter().collect();
+        variables.sort();
+        let items: Vec<ListItem> = variables
+            .into_iter()
+            .map(|(name, value)| {
+                ListItem::new(Spans::from(vec![
+                    Span::styled(name.as_str(), Style::default().fg(Color::Cyan)),
+                    Span::raw(" = "),
+                    Span::raw(value.as_str()),
+                ]))
+            })
+            .collect();
+        let list =
+            List::new(items).block(Block::default().borders(Borders::ALL).title("Variables"));
+        let popup = popup_area(f.size(), 60, 40, 30, 8);
+        f.render_widget(Clear, popup);
+        f.render_widget(list, popup);
+    }
+
+    if let Some(trace) = app.variable_trace.as_ref() {
+        let items: Vec<ListItem> = trace
+            .iter()
+            .map(|r| {
+                let color = match r.source {
+                    VariableSource::Unresolved => Color::Red,
+                    _ if r.value.is_err() => Color::Red,
+                    _ => Color::Cyan,
+                };
+                ListItem::new(Spans::from(vec![
+                    Span::styled(
+                        format!("{{{{{}}}}}", r.placeholder),
+                        Style::default().fg(color),
+                    ),
+                    Span::raw(" = "),
+                    Span::raw(r.display_value()),
+                    Span::styled(
+                        format!(" ({})", r.source),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect();
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Variable resolution"),
+        );
+        let popup = popup_area(f.size(), 70, 40, 40, 8);
+        f.render_widget(Clear, popup);
+        f.render_widget(list, popup);
+    }
+
+    if let Some(text) = app.copy_fallback.as_deref() {
+        let paragraph = Paragraph::new(text).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Clipboard unavailable, copy by hand"),
+        );
+        let popup = popup_area(f.size(), 80, 40, 40, 8);
+        f.render_widget(Clear, popup);
+        f.render_widget(paragraph, popup);
+    }
+
+    if app.stats_open {
+        let popup = popup_area(f.size(), 50, 0, 40, 9);
+        f.render_widget(Clear, popup);
+        f.render_widget(draw_stats(&app.stats), popup);
+    }
+
+    if let Some(diagnosis) = app.diagnosis.as_ref() {
+        let popup = popup_area(f.size(), 50, 0, 46, 12);
+        f.render_widget(Clear, popup);
+        f.render_widget(draw_diagnosis(diagnosis), popup);
+    }
+
+    if app.help_open {
+        let popup = popup_area(f.size(), 60, 0, 50, KEY_BINDINGS.len() as u16 + 2);
+        f.render_widget(Clear, popup);
+        f.render_widget(draw_help(), popup);
+    }
+
+    if let Some(diff) = app.binary_diff.as_ref() {
+        let popup = popup_area(f.size(), 80, 80, 40, 10);
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(popup);
+        let (old, new) = draw_binary_diff(diff);
+        f.render_widget(Clear, popup);
+        f.render_widget(
+            Paragraph::new(old)
+                .block(Block::default().borders(Borders::ALL).title("Previous"))
+                .wrap(Wrap { trim: false }),
+            columns[0],
+        );
+        f.render_widget(
+            Paragraph::new(new)
+                .block(Block::default().borders(Borders::ALL).title("Latest"))
+                .wrap(Wrap { trim: false }),
+            columns[1],
+        );
+    }
+
+    if app.confirm_exit {
+        let text = exit_prompt(app.is_pending(), app.edited_bodies());
+        let confirm = Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL).title("Quit"))
+            .wrap(Wrap { trim: true });
+        let popup = popup_area(f.size(), 40, 0, 38, 4);
+        f.render_widget(Clear, popup);
+        f.render_widget(confirm, popup);
+    }
+
+    if app.symbols.ascii_borders {
+        f.render_widget(AsciiBorders, f.size());
+    }
+}
+
+/// Question of the exit confirmation, telling what would be lost
+fn exit_prompt(pending: bool, edited: usize) -> String {
+    let edited = match edited {
+        0 => None,
+        1 => Some("1 edited body is not saved".to_string()),
+        n => Some(format!("{} edited bodies are not saved", n)),
+    };
+    match (pending, edited) {
+        (true, Some(edited)) => format!("A request is pending and {}. Quit? (y/n)", edited),
+        (false, Some(edited)) => format!("{}. Quit? (y/n)", edited),
+        _ => "A request is pending. Quit? (y/n)".to_string(),
+    }
+}
+
+/// Key bindings listed in the help popup
+const KEY_BINDINGS: [(&str, &str); 37] = [
+    ("Enter", "Send the selected request"),
+    ("Tab", "Switch between requests and response"),
+    ("< / >", "Shrink / grow the requests pane"),
+    ("Up/Down", "Select request / scroll response"),
+    ("Ctrl+F", "Search requests, n/N for next/previous match"),
+    ("Ctrl+T", "Insert a snippet"),
+    ("Ctrl+E", "Switch environment"),
+    (
+        "Ctrl+G / *",
+        "Send a favorite request / add the selected one",
+    ),
+    ("e / E", "Edit the request body / restore the original"),
+    ("c / y", "Copy the request as a curl command"),
+    ("Y", "Copy the request as an HTTPie command"),
+    ("C", "Copy the request and response for a bug report"),
+    ("o", "Open the request url in the browser"),
+    (":", "Send an ad-hoc request"),
+    ("H", "Jump to a response header"),
+    ("/", "Search the response, n/N for next/previous match"),
+    ("h", "Filter the response headers, Esc to clear"),
+    ("{ / }", "Jump to the response headers / body"),
+    ("b", "Jump to the end of the headers"),
+    (
+        "p / P",
+        "Jump to the next / previous part of a multipart response",
+    ),
+    ("s", "Save the response body"),
+    (
+        "R",
+        "Format a response too large to be formatted by default",
+    ),
+    ("r", "Toggle between formatted and raw responses"),
+    ("F", "Follow the end of the response, like tail -f"),
+    ("x", "Extract a response value to a variable"),
+    ("B", "Diff the last two binary responses"),
+    ("[ / ]", "Previous / next variant of an accept matrix"),
+    ("V", "Show variables"),
+    ("S", "Show session statistics"),
+    ("T", "Show what the placeholders of the request resolve to"),
+    (
+        "D",
+        "Send the request 20 times and tally statuses and timings",
+    ),
+    ("?", "Show this help"),
+    ("i / v", "Vim mode: command line / select response lines"),
+    ("Esc", "Close popup / clear search"),
+    ("q", "Quit"),
+    ("Ctrl+C", "Quit, twice with a pending request"),
+    ("Mouse", "Click a pane to focus it"),
+];
+
+fn draw_help() -> Paragraph<'static> {
+    let lines: Vec<Spans> = KEY_BINDINGS
+        .iter()
+        .map(|(key, description)| {
+            Spans::from(vec![
+                Span::styled(format!("{:<10}", key), Style::default().fg(Color::Cyan)),
+                Span::raw(*description),
+            ])
+        })
+        .collect();
+    Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Help"))
 }
 
-fn draw_request(req: &'_ HttpRequest) -> Vec<Spans<'_>> {
-    let mut spans = vec![Spans::from(vec![
-        Span::styled(req.method.to_string(), Style::default().fg(Color::Green)),
-        Span::raw(format!(" {} HTTP/{}", req.url, req.version)),
-    ])];
+fn draw_stats(stats: &SessionStats) -> Paragraph<'_> {
+    let duration =
+        |d: Option<Duration>| d.map_or("-".to_string(), |d| format!("{}ms", d.as_millis()));
+    let request = |r: &Option<(String, Duration)>| {
+        r.as_ref().map_or("-".to_string(), |(url, d)| {
+            format!("{}ms {}", d.as_millis(), url)
+        })
+    };
+    let lines = vec![
+        format!("Requests:       {}", stats.total),
+        format!(
+            "Success rate:   {:.1}% ({} errors)",
+            stats.success_rate(),
+            stats.error
+        ),
+        format!("Average time:   {}", duration(stats.average())),
+        format!("Median time:    {}", duration(stats.median())),
+        format!("Slowest:        {}", request(&stats.slowest)),
+        format!("Fastest:        {}", request(&stats.fastest)),
+        format!("Bytes received: {}", stats.total_bytes),
+    ];
+    Paragraph::new(lines.into_iter().map(Spans::from).collect::<Vec<_>>())
+        .block(Block::default().borders(Borders::ALL).title("Statistics"))
+}
+
+/// Outcomes of the repeated runs by status. Statuses other than 2xx and 3xx are red,
+/// and the title warns when the runs ended with different statuses
+fn draw_diagnosis(diagnosis: &Diagnosis) -> Paragraph<'_> {
+    let mut lines = vec![
+        Spans::from(diagnosis.url.as_str()),
+        Spans::from(""),
+        Spans::from(Span::styled(
+            format!(
+                "{:<8}{:>6}{:>10}{:>10}{:>10}",
+                "Status", "Count", "Min", "Max", "Avg"
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    lines.extend(summarize(&diagnosis.outcomes).iter().map(|row| {
+        let ok = row.status.is_some_and(|status| status < 400);
+        let ms = |d: Duration| format!("{}ms", d.as_millis());
+        Spans::from(Span::styled(
+            format!(
+                "{:<8}{:>6}{:>10}{:>10}{:>10}",
+                row.status.map_or("error".to_string(), |s| s.to_string()),
+                row.count,
+                ms(row.min),
+                ms(row.max),
+                ms(row.avg)
+            ),
+            Style::default().fg(if ok { Color::Green } else { Color::Red }),
+        ))
+    }));
+
+    let mut title = vec![Span::raw(format!(
+        "Diagnosis {}/{}",
+        diagnosis.outcomes.len(),
+        RUNS
+    ))];
+    if diagnosis.flaky() {
+        title.push(Span::styled(
+            " flaky",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    } else if diagnosis.done() {
+        title.push(Span::styled(" stable", Style::default().fg(Color::Green)));
+    }
+    Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Spans::from(title)),
+    )
+}
+
+/// Centered area of `percent_x`/`percent_y` of `area` with a minimum size in columns and rows
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16, min_width: u16, min_height: u16) -> Rect {
+    let width = (area.width * percent_x / 100)
+        .max(min_width)
+        .min(area.width);
+    let height = (area.height * percent_y / 100)
+        .max(min_height)
+        .min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Duration of each spinner frame. The frame is derived from the elapsed time
+/// so the animation speed doesn't depend on the tick rate
+const SPINNER_FRAME: Duration = Duration::from_millis(100);
+
+fn spinner_frame(frames: &'static [&'static str], elapsed: Duration) -> &'static str {
+    let frame = elapsed.as_millis() / SPINNER_FRAME.as_millis();
+    frames[frame as usize % frames.len()]
+}
+
+/// Hex dumps of the old and new bytes. Unchanged bytes are dimmed, deleted bytes
+/// are red in the old column and inserted bytes are green in the new one
+fn draw_binary_diff(diff: &[DiffChunk]) -> (Spans<'static>, Spans<'static>) {
+    let hex = |bytes: &[u8], style: Style| {
+        Span::styled(
+            bytes
+                .iter()
+                .map(|b| format!("{:02x} ", b))
+                .collect::<String>(),
+            style,
+        )
+    };
+    let mut old = Vec::new();
+    let mut new = Vec::new();
+    for chunk in diff {
+        match chunk {
+            DiffChunk::Equal(bytes) => {
+                let style = Style::default().add_modifier(Modifier::DIM);
+                old.push(hex(bytes, style));
+                new.push(hex(bytes, style));
+            }
+            DiffChunk::Delete(bytes) => old.push(hex(bytes, Style::default().fg(Color::Red))),
+            DiffChunk::Insert(bytes) => new.push(hex(bytes, Style::default().fg(Color::Green))),
+        }
+    }
+    (Spans::from(old), Spans::from(new))
+}
+
+/// Summary of the request that produced the response
+fn response_title(req: Option<&HttpRequest>) -> String {
+    req.map(|req| format!("{} {}", req.method, req.url))
+        .unwrap_or_default()
+}
+
+/// Color of a status by class: 2xx green, 3xx cyan, 4xx yellow and 5xx red
+fn status_code_color(status: u16) -> Color {
+    match status {
+        200..=299 => Color::Green,
+        300..=399 => Color::Cyan,
+        400..=499 => Color::Yellow,
+        500..=599 => Color::Red,
+        _ => Color::Gray,
+    }
+}
+
+/// Foreground of the body of error responses, see [`Config::tint_response_body`].
+/// Successful bodies keep the default color
+///
+/// [`Config::tint_response_body`]: crate::config::Config::tint_response_body
+fn body_tint(status: u16) -> Option<Color> {
+    match status_code_color(status) {
+        Color::Yellow => Some(Color::LightYellow),
+        Color::Red => Some(Color::LightRed),
+        _ => None,
+    }
+}
+
+/// `selection` is the range of lines selected in visual mode.
+/// `tint` colors the lines after the headers, and `json` highlights them as JSON.
+/// The occurrences of `query` are highlighted
+fn draw_response<'a>(
+    response: &'a str,
+    trim: bool,
+    scroll: u16,
+    selection: Option<(u16, u16)>,
+    tint: Option<Color>,
+    json: bool,
+    query: Option<&str>,
+) -> Paragraph<'a> {
+    // The response time ends the status line, e.g. `HTTP/1.1 200 OK — 143ms`
+    let elapsed_at = response.lines().next().and_then(|line| line.rfind(" — "));
+    let plain = selection.is_none() && tint.is_none() && !json && query.is_none();
+    let text = if plain && elapsed_at.is_none() {
+        Text::from(response)
+    } else {
+        // The first empty line separates the headers from the body
+        let body_start = response.lines().position(str::is_empty);
+        Text::from(
+            response
+                .lines()
+                .enumerate()
+                .map(|(i, line)| {
+                    let body = body_start.is_some_and(|start| i > start);
+                    let mut style = Style::default();
+                    if let Some(color) = tint.filter(|_| body) {
+                        style = style.fg(color);
+                    }
+                    if selection.is_some_and(|(first, last)| {
+                        (usize::from(first)..=usize::from(last)).contains(&i)
+                    }) {
+                        style = style.bg(Color::Blue);
+                    }
+                    let spans = if json && body {
+                        highlight_json_line(line, style)
+                    } else if let Some(at) = elapsed_at.filter(|_| i == 0) {
+                        let (status, elapsed) = line.split_at(at);
+                        vec![
+                            Span::styled(status, style),
+                            Span::styled(elapsed, style.add_modifier(Modifier::DIM)),
+                        ]
+                    } else {
+                        vec![Span::styled(line, style)]
+                    };
+                    match query {
+                        Some(query) => Spans::from(highlight_matches(spans, line, query)),
+                        None => Spans::from(spans),
+                    }
+                })
+                .collect::<Vec<_>>(),
+        )
+    };
+    Paragraph::new(text).wrap(Wrap { trim }).scroll((scroll, 0))
+}
+
+/// Restyles the parts of `spans`, which make up `line`, matching `query`
+fn highlight_matches<'a>(spans: Vec<Span<'a>>, line: &str, query: &str) -> Vec<Span<'a>> {
+    let ranges = match_ranges(line, query);
+    if ranges.is_empty() {
+        return spans;
+    }
+    let matched = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let mut ret = Vec::new();
+    let mut start = 0;
+    for span in spans {
+        let end = start + span.content.len();
+        let mut at = start;
+        for &(from, to) in ranges
+            .iter()
+            .filter(|(from, to)| *from < end && *to > start)
+        {
+            let (from, to) = (from.max(start), to.min(end));
+            if at < from {
+                let text = span.content[at - start..from - start].to_string();
+                ret.push(Span::styled(text, span.style));
+            }
+            let text = span.content[from - start..to - start].to_string();
+            ret.push(Span::styled(text, span.style.patch(matched)));
+            at = to;
+        }
+        if at < end {
+            ret.push(Span::styled(
+                span.content[at - start..].to_string(),
+                span.style,
+            ));
+        }
+        start = end;
+    }
+    ret
+}
+
+/// Colors the tokens of a line of indented JSON over `base`: keys in cyan, strings in yellow,
+/// numbers in magenta, booleans and null in red
+fn highlight_json_line(line: &str, base: Style) -> Vec<Span<'_>> {
+    let bytes = line.as_bytes();
+    let mut spans = Vec::new();
+    let (mut plain_start, mut i) = (0, 0);
+    while i < bytes.len() {
+        let start = i;
+        let color = match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    // Escaped characters are ASCII, e.g. `\"`
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                if line[i..].trim_start().starts_with(':') {
+                    Color::Cyan
+                } else {
+                    Color::Yellow
+                }
+            }
+            b'-' | b'0'..=b'9' => {
+                while i < bytes.len() && (bytes[i].is_ascii_digit() || b"+-.eE".contains(&bytes[i]))
+                {
+                    i += 1;
+                }
+                Color::Magenta
+            }
+            b if b.is_ascii_alphabetic() => {
+                while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                if !matches!(&line[start..i], "true" | "false" | "null") {
+                    continue;
+                }
+                Color::Red
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        if plain_start < start {
+            spans.push(Span::styled(&line[plain_start..start], base));
+        }
+        spans.push(Span::styled(&line[start..i], base.fg(color)));
+        plain_start = i;
+    }
+    if plain_start < line.len() {
+        spans.push(Span::styled(&line[plain_start..], base));
+    }
+    spans
+}
+
+/// Hint shown before the first response, listing the variables the selected request lacks
+fn draw_empty_response(unresolved: &[String]) -> Vec<Spans<'static>> {
+    let mut lines = vec![Spans::from(Span::styled(
+        "Press Enter to send the selected request",
+        Style::default().fg(Color::DarkGray),
+    ))];
+    if !unresolved.is_empty() {
+        lines.push(Spans::from(""));
+        lines.push(Spans::from(Span::styled(
+            "Unresolved variables:",
+            Style::default().fg(Color::Yellow),
+        )));
+        lines.extend(
+            unresolved
+                .iter()
+                .map(|name| Spans::from(format!("  {{{{{}}}}}", name))),
+        );
+    }
+    lines
+}
+
+fn draw_search_bar(query: &str, matches: usize) -> Paragraph<'_> {
+    Paragraph::new(Spans::from(vec![
+        Span::styled("/", Style::default().fg(Color::Yellow)),
+        Span::raw(query),
+        Span::styled(
+            format!("  [{} matches]", matches),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]))
+}
+
+fn draw_status_bar(app: &App, area: Rect) -> Paragraph<'_> {
+    let position = match app.focus {
+        Focus::Requests => format_position(
+            "request",
+            app.list.selected().unwrap_or_default() + 1,
+            app.requests.len(),
+        ),
+        Focus::Response => format_position(
+            "line",
+            app.response_scroll as usize + 1,
+            app.response_lines(),
+        ),
+    };
+    let follow = match app.follow {
+        Follow::Off => "",
+        Follow::On => "[follow] ",
+        Follow::Paused => "[paused] ",
+    };
+    let environment = app
+        .environment
+        .as_ref()
+        .map(|env| format!("[{}] ", env))
+        .unwrap_or_default();
+    let summary = app.assertion_summary();
+    let (passed, failed) = if summary.passed + summary.failed > 0 {
+        (
+            format!("{} passed ", summary.passed),
+            format!("{} failed ", summary.failed),
+        )
+    } else {
+        Default::default()
+    };
+    let mode = app.vim_mode.map_or(Span::raw(""), |mode| {
+        let color = match mode {
+            VimMode::Normal => Color::Green,
+            VimMode::Visual => Color::Blue,
+            VimMode::Insert => Color::Yellow,
+        };
+        Span::styled(
+            format!(" {} ", mode.label()),
+            Style::default()
+                .fg(Color::Black)
+                .bg(color)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
+    let message = app.message.as_deref().unwrap_or_default();
+    let width = mode.width()
+        + follow.len()
+        + passed.len()
+        + failed.len()
+        + environment.len()
+        + position.len()
+        + message.len();
+    Paragraph::new(Spans::from(vec![
+        mode,
+        Span::styled(message, Style::default().fg(Color::Yellow)),
+        Span::raw(" ".repeat(usize::from(area.width).saturating_sub(width))),
+        Span::styled(passed, Style::default().fg(Color::Green)),
+        Span::styled(failed, Style::default().fg(Color::Red)),
+        Span::styled(follow, Style::default().fg(Color::Magenta)),
+        Span::styled(environment, Style::default().fg(Color::Cyan)),
+        Span::styled(position, Style::default().fg(Color::DarkGray)),
+    ]))
+}
+
+/// Formats a 1-based `current` position, e.g. `line 3 of 10`
+fn format_position(label: &str, current: usize, total: usize) -> String {
+    format!("{} {} of {}", label, current.min(total), total)
+}
+
+fn contains(chunk: Rect, (x, y): (u16, u16)) -> bool {
+    chunk.x <= x && x < chunk.x + chunk.width && chunk.y <= y && y < chunk.y + chunk.height
+}
+
+/// Splits `text` in spans, making bold and underlined the chars at `positions`.
+/// `positions` are relative to the start of `text` plus `offset`
+fn highlight(text: &str, offset: usize, positions: &[usize], style: Style) -> Vec<Span<'static>> {
+    let matched = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans: Vec<Span> = Vec::new();
+    let mut current = String::new();
+    let mut current_matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let is_matched = positions.contains(&(offset + i));
+        if is_matched != current_matched && !current.is_empty() {
+            let style = if current_matched { matched } else { style };
+            spans.push(Span::styled(std::mem::take(&mut current), style));
+        }
+        current_matched = is_matched;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        spans.push(Span::styled(
+            current,
+            if current_matched { matched } else { style },
+        ));
+    }
+    spans
+}
+
+/// `matched` are the chars of the [`request_line`](crate::app::request_line) matched by the search
+fn draw_request<'a>(
+    req: &'a HttpRequest,
+    nerd_font_icons: bool,
+    pretty_body: bool,
+    matched: &[usize],
+) -> Vec<Spans<'a>> {
+    let method = req.method.to_string();
+    let mut request_line = Vec::new();
+    let icon = method_icon(&req.method, nerd_font_icons);
+    if !icon.is_empty() {
+        request_line.push(Span::styled(
+            format!("{} ", icon),
+            Style::default().fg(Color::Green),
+        ));
+    }
+    request_line.extend(highlight(
+        &method,
+        0,
+        matched,
+        Style::default().fg(Color::Green),
+    ));
+    request_line.extend(highlight(
+        &format!(" {}", req.url),
+        method.chars().count(),
+        matched,
+        Style::default(),
+    ));
+    if !req.version.is_empty() {
+        request_line.push(Span::raw(format!(" HTTP/{}", req.version)));
+    }
+    if req.basic_auth.is_some() {
+        request_line.push(Span::styled(" [auth]", Style::default().fg(Color::Yellow)));
+    }
+    if req.body_override.is_some() {
+        request_line.push(Span::styled(
+            " [edited]",
+            Style::default().fg(Color::Magenta),
+        ));
+    }
+    let mut spans = vec![Spans::from(request_line)];
+
+    spans.extend(req.query.iter().map(|(k, v)| {
+        Spans::from(vec![
+            Span::styled("? ", Style::default().fg(Color::DarkGray)),
+            Span::raw(format!("{} = {}", k, v)),
+        ])
+    }));
 
     let headers: Vec<Spans> = req
         .headers
@@ -129,12 +1033,340 @@ fn draw_request(req: &'_ HttpRequest) -> Vec<Spans<'_>> {
     spans.extend(headers);
     // new line
     spans.push(Spans::from(""));
-    if !req.body.is_empty() {
+    if let Some(path) = &req.body_file {
         spans.push(Spans::from(Span::styled(
-            req.body.as_str(),
+            format!("< {}", path.display()),
             Style::default().fg(Color::Rgb(246, 69, 42)),
         )));
         spans.push(Spans::from(""));
+    } else if !req.body.is_empty() {
+        let style = Style::default().fg(Color::Rgb(246, 69, 42));
+        // Only the displayed body is formatted, the request is sent as written
+        match indent_json(&req.body, DEFAULT_INDENT)
+            .ok()
+            .filter(|_| pretty_body)
+        {
+            Some(body) => spans.extend(
+                body.lines()
+                    .map(|line| Spans::from(Span::styled(line.to_string(), style))),
+            ),
+            None => spans.push(Spans::from(Span::styled(req.body.as_str(), style))),
+        }
+        spans.push(Spans::from(""));
     }
     spans
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        body_tint, draw_empty_response, draw_request, draw_response, exit_prompt, format_position,
+        highlight, highlight_json_line, highlight_matches, idle_expired, layout_mode, popup_area,
+        response_title, spinner_frame, LayoutMode,
+    };
+    use crate::symbols::ASCII;
+    use rq_core::parser::parse;
+    use std::time::{Duration, Instant};
+    use tui::{
+        backend::TestBackend,
+        buffer::Buffer,
+        layout::Rect,
+        style::{Color, Modifier, Style},
+        text::{Span, Spans},
+        Terminal,
+    };
+
+    fn render_response(response: &str, trim: bool) -> Buffer {
+        render_selection(response, trim, None)
+    }
+
+    fn render_selection(response: &str, trim: bool, selection: Option<(u16, u16)>) -> Buffer {
+        render_tinted(response, trim, selection, None)
+    }
+
+    fn render_tinted(
+        response: &str,
+        trim: bool,
+        selection: Option<(u16, u16)>,
+        tint: Option<Color>,
+    ) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(12, 3)).unwrap();
+        terminal
+            .draw(|f| {
+                f.render_widget(
+                    draw_response(response, trim, 0, selection, tint, false, None),
+                    f.size(),
+                )
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    #[test]
+    fn test_layout_mode() {
+        let cases = [
+            (Rect::new(0, 0, 200, 50), LayoutMode::Horizontal),
+            (Rect::new(0, 0, 80, 80), LayoutMode::Horizontal),
+            (Rect::new(0, 0, 70, 100), LayoutMode::Vertical),
+            (Rect::new(0, 0, 59, 20), LayoutMode::Single),
+            (Rect::new(0, 0, 20, 80), LayoutMode::Single),
+        ];
+        for (area, expected) in cases {
+            assert_eq!(layout_mode(area), expected, "{:?}", area);
+        }
+    }
+
+    #[test]
+    fn test_popup_area() {
+        let area = Rect::new(0, 0, 200, 100);
+        assert_eq!(popup_area(area, 40, 25, 30, 8), Rect::new(60, 37, 80, 25));
+
+        let area = Rect::new(0, 0, 50, 20);
+        assert_eq!(popup_area(area, 40, 25, 30, 8), Rect::new(10, 6, 30, 8));
+
+        let area = Rect::new(0, 0, 20, 5);
+        assert_eq!(popup_area(area, 40, 25, 30, 8), area);
+    }
+
+    #[test]
+    fn test_response_wrap_trim() {
+        let json = "{\n  \"a\": 1\n}";
+        assert_eq!(
+            render_response(json, false),
+            Buffer::with_lines(vec!["{           ", "  \"a\": 1    ", "}           "])
+        );
+        assert_eq!(
+            render_response(json, true),
+            Buffer::with_lines(vec!["{           ", "\"a\": 1      ", "}           "])
+        );
+    }
+
+    #[test]
+    fn test_response_visual_selection() {
+        let buffer = render_selection("a\nb\nc", false, Some((1, 2)));
+        assert_eq!(buffer.get(0, 0).bg, Color::Reset);
+        assert_eq!(buffer.get(0, 1).bg, Color::Blue);
+        assert_eq!(buffer.get(0, 2).bg, Color::Blue);
+    }
+
+    #[test]
+    fn test_highlight_json_line() {
+        let tokens = |line| -> Vec<(String, Color)> {
+            highlight_json_line(line, Style::default())
+                .into_iter()
+                .map(|span| {
+                    (
+                        span.content.to_string(),
+                        span.style.fg.unwrap_or(Color::Reset),
+                    )
+                })
+                .collect()
+        };
+        let expected = |spans: &[(&str, Color)]| -> Vec<(String, Color)> {
+            spans.iter().map(|(s, c)| (s.to_string(), *c)).collect()
+        };
+        assert_eq!(
+            tokens(r#"  "na\"me": "ünï \"rq\"","#),
+            expected(&[
+                ("  ", Color::Reset),
+                (r#""na\"me""#, Color::Cyan),
+                (": ", Color::Reset),
+                (r#""ünï \"rq\"""#, Color::Yellow),
+                (",", Color::Reset),
+            ])
+        );
+        assert_eq!(
+            tokens("[-1.5e3, true, null]"),
+            expected(&[
+                ("[", Color::Reset),
+                ("-1.5e3", Color::Magenta),
+                (", ", Color::Reset),
+                ("true", Color::Red),
+                (", ", Color::Reset),
+                ("null", Color::Red),
+                ("]", Color::Reset),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_highlight_matches() {
+        let red = Style::default().fg(Color::Red);
+        let spans = vec![Span::raw("an Err"), Span::styled("or: error", red)];
+        let highlighted: Vec<_> = highlight_matches(spans, "an Error: error", "ERROR")
+            .into_iter()
+            .map(|span| (span.content.to_string(), span.style.bg))
+            .collect();
+        assert_eq!(
+            highlighted,
+            [
+                ("an ".to_string(), None),
+                ("Err".to_string(), Some(Color::Yellow)),
+                ("or".to_string(), Some(Color::Yellow)),
+                (": ".to_string(), None),
+                ("error".to_string(), Some(Color::Yellow)),
+            ]
+        );
+        assert_eq!(
+            highlight_matches(vec![Span::raw("ok")], "ok", "no").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_body_tint() {
+        assert_eq!(body_tint(200), None);
+        assert_eq!(body_tint(304), None);
+        assert_eq!(body_tint(404), Some(Color::LightYellow));
+        assert_eq!(body_tint(503), Some(Color::LightRed));
+
+        let buffer = render_tinted("500\n\nerror", false, None, body_tint(500));
+        assert_eq!(buffer.get(0, 0).fg, Color::Reset);
+        assert_eq!(buffer.get(0, 2).fg, Color::LightRed);
+    }
+
+    #[test]
+    fn test_response_time_is_dimmed() {
+        let buffer = render_tinted("200 — 5ms\n\nok", false, None, None);
+        assert!(!buffer.get(2, 0).modifier.contains(Modifier::DIM));
+        assert!(buffer.get(6, 0).modifier.contains(Modifier::DIM));
+        assert!(!buffer.get(0, 2).modifier.contains(Modifier::DIM));
+    }
+
+    /// Content of each line
+    fn text(lines: Vec<Spans>) -> Vec<String> {
+        lines
+            .iter()
+            .map(|spans| spans.0.iter().map(|span| span.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_draw_empty_response() {
+        assert_eq!(
+            text(draw_empty_response(&[])),
+            ["Press Enter to send the selected request"]
+        );
+
+        let req = &parse("GET https://{{host}}/users/{{id}} HTTP/1.1\n\n")
+            .unwrap()
+            .requests[0];
+        assert_eq!(
+            text(draw_empty_response(&req.unresolved_variables())),
+            [
+                "Press Enter to send the selected request",
+                "",
+                "Unresolved variables:",
+                "  {{host}}",
+                "  {{id}}"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_draw_request_pretty_body() {
+        let file =
+            parse("POST https://api.dev/users HTTP/1.1\n\n{\"name\":\"rq\",\"tags\":[1]}\n\n")
+                .unwrap();
+        let req = &file.requests[0];
+
+        assert_eq!(
+            text(draw_request(req, false, true, &[]))[2..],
+            [
+                "{",
+                "  \"name\": \"rq\",",
+                "  \"tags\": [",
+                "    1",
+                "  ]",
+                "}",
+                ""
+            ]
+        );
+        assert_eq!(req.body, "{\"name\":\"rq\",\"tags\":[1]}");
+
+        assert_eq!(
+            text(draw_request(req, false, false, &[]))[2..],
+            ["{\"name\":\"rq\",\"tags\":[1]}", ""]
+        );
+    }
+
+    #[test]
+    fn test_response_title() {
+        let file = parse("PUT https://api.dev/items/1 HTTP/1.1\n\n").unwrap();
+        assert_eq!(
+            response_title(file.requests.first()),
+            "PUT https://api.dev/items/1"
+        );
+        assert_eq!(response_title(None), "");
+    }
+
+    #[test]
+    fn test_idle_expired() {
+        let last_input = Instant::now();
+        let idle = Some(Duration::from_secs(60));
+        assert!(!idle_expired(last_input, last_input, idle));
+        assert!(!idle_expired(
+            last_input,
+            last_input + Duration::from_secs(59),
+            idle
+        ));
+        assert!(idle_expired(
+            last_input,
+            last_input + Duration::from_secs(60),
+            idle
+        ));
+        assert!(!idle_expired(
+            last_input,
+            last_input + Duration::from_secs(3600),
+            None
+        ));
+    }
+
+    #[test]
+    fn test_spinner_frame() {
+        assert_eq!(spinner_frame(ASCII.spinner, Duration::from_millis(0)), "|");
+        assert_eq!(spinner_frame(ASCII.spinner, Duration::from_millis(99)), "|");
+        assert_eq!(
+            spinner_frame(ASCII.spinner, Duration::from_millis(100)),
+            "/"
+        );
+        assert_eq!(
+            spinner_frame(ASCII.spinner, Duration::from_millis(250)),
+            "-"
+        );
+        assert_eq!(
+            spinner_frame(ASCII.spinner, Duration::from_millis(420)),
+            "|"
+        );
+    }
+
+    #[test]
+    fn test_exit_prompt() {
+        assert_eq!(exit_prompt(true, 0), "A request is pending. Quit? (y/n)");
+        assert_eq!(
+            exit_prompt(false, 1),
+            "1 edited body is not saved. Quit? (y/n)"
+        );
+        assert_eq!(
+            exit_prompt(true, 2),
+            "A request is pending and 2 edited bodies are not saved. Quit? (y/n)"
+        );
+    }
+
+    #[test]
+    fn test_format_position() {
+        assert_eq!(format_position("line", 3, 10), "line 3 of 10");
+        assert_eq!(format_position("request", 1, 1), "request 1 of 1");
+        assert_eq!(format_position("line", 1, 0), "line 0 of 0");
+    }
+
+    #[test]
+    fn test_highlight() {
+        let spans = highlight(" /users", 3, &[4, 5, 9], Style::default());
+        let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(texts, vec![" ", "/u", "ser", "s"]);
+        assert!(spans[1].style.add_modifier.contains(Modifier::UNDERLINED));
+        assert!(!spans[2].style.add_modifier.contains(Modifier::UNDERLINED));
+        assert!(spans[3].style.add_modifier.contains(Modifier::UNDERLINED));
+    }
+}
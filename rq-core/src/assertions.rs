@@ -0,0 +1,88 @@
+use std::fmt::Display;
+
+use crate::{parser::HttpRequest, request::Response};
+
+/// Checks the response against the assertions of the request (`# @expect-status`).
+/// Returns `None` when the request has no assertions.
+pub fn check(req: &HttpRequest, response: &Response) -> Option<bool> {
+    req.expect_status
+        .map(|status| response.status.as_u16() == status)
+}
+
+/// Tally of the assertion results of a batch of requests
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub requests: usize,
+    pub passed: usize,
+    pub failed: usize,
+}
+
+impl Summary {
+    /// Aggregates per-request results, where `None` is a request without assertions
+    pub fn from_results(results: &[Option<bool>]) -> Self {
+        results.iter().fold(Self::default(), |mut summary, result| {
+            summary.requests += 1;
+            match result {
+                Some(true) => summary.passed += 1,
+                Some(false) => summary.failed += 1,
+                None => {}
+            }
+            summary
+        })
+    }
+
+    pub fn success(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} requests, {} passed, {} failed",
+            self.requests, self.passed, self.failed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{check, Summary};
+    use crate::{parser::parse, request::Response};
+
+    #[test]
+    fn test_check() {
+        let file =
+            parse("# @expect-status 201\nPOST /users HTTP/1.1\n\n###\n\nGET /users HTTP/1.1\n\n")
+                .unwrap();
+        let mut response = Response {
+            status: StatusCode::CREATED,
+            ..Default::default()
+        };
+        assert_eq!(check(&file.requests[0], &response), Some(true));
+        assert_eq!(check(&file.requests[1], &response), None);
+
+        response.status = StatusCode::BAD_REQUEST;
+        assert_eq!(check(&file.requests[0], &response), Some(false));
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary::from_results(&[Some(true), Some(false), None, Some(true)]);
+        assert_eq!(
+            summary,
+            Summary {
+                requests: 4,
+                passed: 2,
+                failed: 1
+            }
+        );
+        assert!(!summary.success());
+        assert_eq!(summary.to_string(), "4 requests, 2 passed, 1 failed");
+
+        assert!(Summary::from_results(&[None, Some(true)]).success());
+    }
+}
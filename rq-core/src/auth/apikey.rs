@@ -0,0 +1,122 @@
+use crate::parser::{append_query, HttpRequest};
+
+/// Where the API key is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthVia {
+    Header,
+    Query,
+}
+
+/// API key of `# @auth apikey <header|query> <name> <value>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    pub via: AuthVia,
+    pub name: String,
+    pub value: String,
+}
+
+impl ApiKey {
+    /// Parses `<header|query> <name> <value>`
+    pub fn parse(value: &str) -> Option<Self> {
+        let (via, rest) = value.trim().split_once(char::is_whitespace)?;
+        let via = match via {
+            "header" => AuthVia::Header,
+            "query" => AuthVia::Query,
+            _ => return None,
+        };
+        let (name, value) = rest.trim_start().split_once(char::is_whitespace)?;
+        Some(Self {
+            via,
+            name: name.to_string(),
+            value: value.trim().to_string(),
+        })
+    }
+}
+
+/// Sends `value` as the `key_name` header or query parameter.
+/// An explicit header with the same name takes precedence.
+pub fn inject_apikey(req: &mut HttpRequest, via: AuthVia, key_name: &str, value: &str) {
+    match via {
+        AuthVia::Header if req.has_header(key_name) => {}
+        AuthVia::Header => {
+            req.headers.insert(key_name.to_string(), value.to_string());
+        }
+        AuthVia::Query => {
+            let pair = form_urlencoded::Serializer::new(String::new())
+                .append_pair(key_name, value)
+                .finish();
+            req.url = append_query(&req.url, &pair);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inject_apikey, ApiKey, AuthVia};
+    use crate::{parser::parse, variables::Variables};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ApiKey::parse("header X-API-Key {{apiKey}}"),
+            Some(ApiKey {
+                via: AuthVia::Header,
+                name: "X-API-Key".to_string(),
+                value: "{{apiKey}}".to_string(),
+            })
+        );
+        assert_eq!(ApiKey::parse("cookie k v"), None);
+        assert_eq!(ApiKey::parse("query apikey"), None);
+    }
+
+    #[test]
+    fn test_inject_header() {
+        let mut req = parse("GET https://api.dev/users HTTP/1.1\n\n")
+            .unwrap()
+            .requests[0]
+            .clone();
+        inject_apikey(&mut req, AuthVia::Header, "X-API-Key", "secret");
+        assert_eq!(req.headers["X-API-Key"], "secret");
+
+        inject_apikey(&mut req, AuthVia::Header, "x-api-key", "other");
+        assert_eq!(req.headers.len(), 1);
+    }
+
+    #[test]
+    fn test_inject_query() {
+        let file = parse(
+            "GET https://api.dev/users HTTP/1.1\n\n###\n\nGET https://api.dev/users?page=2 HTTP/1.1\n\n",
+        )
+        .unwrap();
+        let mut req = file.requests[0].clone();
+        inject_apikey(&mut req, AuthVia::Query, "apikey", "s3cr&t");
+        assert_eq!(req.url, "https://api.dev/users?apikey=s3cr%26t");
+
+        let mut req = file.requests[1].clone();
+        inject_apikey(&mut req, AuthVia::Query, "apikey", "secret");
+        assert_eq!(req.url, "https://api.dev/users?page=2&apikey=secret");
+    }
+
+    #[test]
+    fn test_value_is_resolved_before_injection() {
+        let file = parse(
+            "# @auth apikey header X-API-Key {{apiKey}}
+GET https://api.dev/users HTTP/1.1
+
+###
+
+# @auth apikey query apikey {{apiKey}}
+GET https://api.dev/users HTTP/1.1
+
+",
+        )
+        .unwrap();
+        let variables = Variables::from([("apiKey".to_string(), "secret".to_string())]);
+
+        let req = file.requests[0].with_variables(&variables);
+        assert_eq!(req.headers["X-API-Key"], "secret");
+
+        let req = file.requests[1].with_variables(&variables);
+        assert_eq!(req.url, "https://api.dev/users?apikey=secret");
+    }
+}
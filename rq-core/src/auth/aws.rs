@@ -0,0 +1,600 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::{Display, Write},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime},
+};
+
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    Url,
+};
+use sha2::{Digest, Sha256};
+
+/// Credentials are read again when they expire within this window
+const EXPIRATION_WINDOW: Duration = Duration::from_secs(60);
+
+const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Present for temporary credentials, e.g. assumed roles
+    pub session_token: Option<String>,
+    pub expiration: Option<SystemTime>,
+}
+
+impl AwsCredentials {
+    fn expires_soon(&self, now: SystemTime) -> bool {
+        self.expiration
+            .is_some_and(|expiration| expiration <= now + EXPIRATION_WINDOW)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AwsError {
+    /// The profile doesn't exist or lacks an access key or secret key
+    MissingCredentials(String),
+    InvalidExpiration(String),
+}
+
+impl Display for AwsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingCredentials(profile) => {
+                write!(f, "no AWS credentials found for profile {}", profile)
+            }
+            Self::InvalidExpiration(value) => write!(f, "invalid aws_expiration {}", value),
+        }
+    }
+}
+
+impl std::error::Error for AwsError {}
+
+static CACHE: OnceLock<Mutex<HashMap<String, AwsCredentials>>> = OnceLock::new();
+
+/// Resolves the AWS credentials for the session.
+///
+/// `RQ_AWS_ACCESS_KEY_ID`, `RQ_AWS_SECRET_ACCESS_KEY` and `RQ_AWS_SESSION_TOKEN` take precedence.
+/// Otherwise the credentials are read from `~/.aws/credentials` and `~/.aws/config` for
+/// `profile` (from `# @aws-profile`), `AWS_PROFILE` or `default`, in that order.
+/// File credentials are cached and read again when they are about to expire.
+pub fn credentials(profile: Option<&str>) -> Result<AwsCredentials, AwsError> {
+    if let Some(credentials) = env_credentials() {
+        return Ok(credentials);
+    }
+
+    let profile = profile
+        .map(str::to_string)
+        .or_else(|| env::var("AWS_PROFILE").ok())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+
+    let mut cache = CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    match cache.get(&profile) {
+        Some(credentials) if !credentials.expires_soon(SystemTime::now()) => {
+            Ok(credentials.clone())
+        }
+        _ => {
+            let credentials =
+                load_profile(&credentials_file_path(), &config_file_path(), &profile)?;
+            cache.insert(profile, credentials.clone());
+            Ok(credentials)
+        }
+    }
+}
+
+fn env_credentials() -> Option<AwsCredentials> {
+    Some(AwsCredentials {
+        access_key_id: env::var("RQ_AWS_ACCESS_KEY_ID").ok()?,
+        secret_access_key: env::var("RQ_AWS_SECRET_ACCESS_KEY").ok()?,
+        session_token: env::var("RQ_AWS_SESSION_TOKEN").ok(),
+        expiration: None,
+    })
+}
+
+fn aws_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_default().join(".aws")
+}
+
+fn credentials_file_path() -> PathBuf {
+    env::var_os("AWS_SHARED_CREDENTIALS_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| aws_dir().join("credentials"))
+}
+
+fn config_file_path() -> PathBuf {
+    env::var_os("AWS_CONFIG_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| aws_dir().join("config"))
+}
+
+/// Reads `profile` from the credentials and config files. Keys in the credentials file win.
+fn load_profile(
+    credentials_path: &Path,
+    config_path: &Path,
+    profile: &str,
+) -> Result<AwsCredentials, AwsError> {
+    let mut values = read_section(config_path, &format!("profile {}", profile));
+    if profile == DEFAULT_PROFILE {
+        values.extend(read_section(config_path, DEFAULT_PROFILE));
+    }
+    values.extend(read_section(credentials_path, profile));
+
+    let missing = || AwsError::MissingCredentials(profile.to_string());
+    let expiration = match values.remove("aws_expiration") {
+        Some(value) => Some(parse_timestamp(&value).ok_or(AwsError::InvalidExpiration(value))?),
+        None => None,
+    };
+
+    Ok(AwsCredentials {
+        access_key_id: values.remove("aws_access_key_id").ok_or_else(missing)?,
+        secret_access_key: values.remove("aws_secret_access_key").ok_or_else(missing)?,
+        session_token: values.remove("aws_session_token"),
+        expiration,
+    })
+}
+
+/// Signs a request of `# @auth aws-sigv4 <region> <service>` with AWS Signature Version 4,
+/// adding the `X-Amz-*` and `Authorization` headers to `headers`. `body` is `None` when it
+/// can't be hashed, e.g. multipart forms, and sent as an unsigned payload
+#[allow(clippy::too_many_arguments)]
+pub fn sign(
+    headers: &mut HeaderMap,
+    method: &str,
+    url: &Url,
+    body: Option<&[u8]>,
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    now: SystemTime,
+) -> Result<(), header::InvalidHeaderValue> {
+    let payload_hash = match body {
+        Some(body) => hex(&Sha256::digest(body)),
+        None => "UNSIGNED-PAYLOAD".to_string(),
+    };
+    let amz_date = format_amz_date(now);
+    headers.insert("x-amz-date", HeaderValue::from_str(&amz_date)?);
+    headers.insert(
+        "x-amz-content-sha256",
+        HeaderValue::from_str(&payload_hash)?,
+    );
+    if let Some(token) = &credentials.session_token {
+        headers.insert("x-amz-security-token", HeaderValue::from_str(token)?);
+    }
+
+    // The `Host` header is added by the client when the request doesn't set one
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let mut signed = vec![("host".to_string(), host)];
+    for (name, value) in headers.iter() {
+        let name = name.as_str();
+        if name == "host" {
+            signed[0].1 = value.to_str().unwrap_or_default().to_string();
+        } else if name.starts_with("x-amz-") || name == "content-type" {
+            signed.push((
+                name.to_string(),
+                value.to_str().unwrap_or_default().to_string(),
+            ));
+        }
+    }
+
+    let authorization = authorization(
+        method,
+        url,
+        &signed,
+        &payload_hash,
+        credentials,
+        region,
+        service,
+        &amz_date,
+    );
+    headers.insert(
+        header::AUTHORIZATION,
+        HeaderValue::from_str(&authorization)?,
+    );
+    Ok(())
+}
+
+/// `Authorization` header value of a request signed with `headers`, lowercase names and
+/// their values
+#[allow(clippy::too_many_arguments)]
+fn authorization(
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    payload_hash: &str,
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    amz_date: &str,
+) -> String {
+    // S3 signs the path as sent, the other services encode it once more
+    let path = match url.path() {
+        "" => "/".to_string(),
+        path if service == "s3" => path.to_string(),
+        path => uri_encode(path, false),
+    };
+    let mut query: Vec<_> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k, true), uri_encode(&v, true)))
+        .collect();
+    query.sort();
+    let query: Vec<_> = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+
+    let mut headers: Vec<_> = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+            (name.to_ascii_lowercase(), value)
+        })
+        .collect();
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = [
+        method,
+        &path,
+        &query.join("&"),
+        &canonical_headers,
+        &signed_headers,
+        payload_hash,
+    ]
+    .join("\n");
+    let date = &amz_date[..8];
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = format!("AWS4{}", credentials.secret_access_key);
+    let key = [date, region, service, "aws4_request"]
+        .iter()
+        .fold(key.into_bytes(), |key, part| {
+            hmac_sha256(&key, part.as_bytes())
+        });
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id, scope, signed_headers, signature
+    )
+}
+
+/// Percent-encodes all but the unreserved characters, and `/` unless `slash` is set
+fn uri_encode(value: &str, slash: bool) -> String {
+    value.bytes().fold(String::new(), |mut out, b| {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            b'/' if !slash => out.push('/'),
+            _ => {
+                let _ = write!(out, "%{:02X}", b);
+            }
+        }
+        out
+    })
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<_>>();
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(data)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{:02x}", b);
+        out
+    })
+}
+
+/// Formats `time` as the UTC `20150830T123600Z` of the `X-Amz-Date` header
+fn format_amz_date(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, seconds) = ((seconds / 86400) as i64, seconds % 86400);
+
+    // Civil date of the days since epoch, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Key-values of the INI `[section]` in the file at `path`
+fn read_section(path: &Path, section: &str) -> HashMap<String, String> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let mut values = HashMap::new();
+    let mut in_section = false;
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.trim() == section;
+        } else if in_section {
+            if let Some((key, value)) = line.split_once('=') {
+                values.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+    values
+}
+
+/// Parses an UTC RFC 3339 timestamp like `2022-10-17T12:30:00Z`
+fn parse_timestamp(value: &str) -> Option<SystemTime> {
+    let value = value
+        .strip_suffix('Z')
+        .or_else(|| value.strip_suffix("+00:00"))?;
+    let (date, time) = value.split_once('T')?;
+
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let mut time = time.splitn(3, ':');
+    let (hour, minute) = (
+        time.next()?.parse::<u64>().ok()?,
+        time.next()?.parse::<u64>().ok()?,
+    );
+    // Fractional seconds are ignored
+    let second = time.next()?.split('.').next()?.parse::<u64>().ok()?;
+
+    // Days since epoch, see http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = u64::try_from(era * 146097 + day_of_era - 719468).ok()?;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        authorization, format_amz_date, load_profile, parse_timestamp, sign, AwsCredentials,
+        AwsError,
+    };
+    use reqwest::{header::HeaderMap, Url};
+    use std::{
+        fs,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    };
+
+    /// Credentials of the AWS Signature Version 4 test suite
+    fn example_credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+            expiration: None,
+        }
+    }
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rq-aws-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_profile() {
+        let credentials = temp_file(
+            "credentials",
+            r#"
+[default]
+aws_access_key_id = AKIDEFAULT
+aws_secret_access_key = default-secret
+
+[assumed]
+aws_access_key_id=AKIASSUMED
+aws_secret_access_key=assumed-secret
+aws_session_token=token
+aws_expiration=2022-10-17T12:30:00Z
+"#,
+        );
+        let config = temp_file(
+            "config",
+            r#"
+[profile from-config]
+region = eu-west-1
+aws_access_key_id = AKICONFIG
+aws_secret_access_key = config-secret
+"#,
+        );
+
+        assert_eq!(
+            load_profile(&credentials, &config, "default").unwrap(),
+            AwsCredentials {
+                access_key_id: "AKIDEFAULT".to_string(),
+                secret_access_key: "default-secret".to_string(),
+                session_token: None,
+                expiration: None,
+            }
+        );
+
+        let assumed = load_profile(&credentials, &config, "assumed").unwrap();
+        assert_eq!(assumed.session_token.as_deref(), Some("token"));
+        assert_eq!(
+            assumed.expiration,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1666009800))
+        );
+        assert!(assumed.expires_soon(SystemTime::now()));
+
+        let from_config = load_profile(&credentials, &config, "from-config").unwrap();
+        assert_eq!(from_config.access_key_id, "AKICONFIG");
+
+        assert_eq!(
+            load_profile(&credentials, &config, "missing"),
+            Err(AwsError::MissingCredentials("missing".to_string()))
+        );
+
+        fs::remove_file(credentials).unwrap();
+        fs::remove_file(config).unwrap();
+    }
+
+    #[test]
+    fn test_authorization() {
+        let headers = [
+            ("Host".to_string(), "example.amazonaws.com".to_string()),
+            ("X-Amz-Date".to_string(), "20150830T123600Z".to_string()),
+        ];
+        let empty_hash = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let authorization = |url: &str| {
+            authorization(
+                "GET",
+                &Url::parse(url).unwrap(),
+                &headers,
+                empty_hash,
+                &example_credentials(),
+                "us-east-1",
+                "service",
+                "20150830T123600Z",
+            )
+        };
+
+        // `get-vanilla` and `get-vanilla-query-order-key-case` of the test suite
+        assert_eq!(
+            authorization("https://example.amazonaws.com/"),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+        assert!(
+            authorization("https://example.amazonaws.com/?Param2=value2&Param1=value1")
+                .ends_with("b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500")
+        );
+    }
+
+    #[test]
+    fn test_sign() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        let credentials = AwsCredentials {
+            session_token: Some("token".to_string()),
+            ..example_credentials()
+        };
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1440938160);
+        let url = Url::parse("http://localhost:4566/queue").unwrap();
+        sign(
+            &mut headers,
+            "POST",
+            &url,
+            Some(b"{}"),
+            &credentials,
+            "eu-west-1",
+            "sqs",
+            now,
+        )
+        .unwrap();
+
+        assert_eq!(headers["x-amz-date"], "20150830T123600Z");
+        assert_eq!(
+            headers["x-amz-content-sha256"],
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+        assert_eq!(headers["x-amz-security-token"], "token");
+        let authorization = headers["authorization"].to_str().unwrap();
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/eu-west-1/sqs/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-content-sha256;x-amz-date;x-amz-security-token, "
+        ));
+    }
+
+    #[test]
+    fn test_format_amz_date() {
+        assert_eq!(format_amz_date(SystemTime::UNIX_EPOCH), "19700101T000000Z");
+        let leap_day = parse_timestamp("2024-02-29T23:59:59Z").unwrap();
+        assert_eq!(format_amz_date(leap_day), "20240229T235959Z");
+    }
+
+    #[test]
+    fn test_expires_soon() {
+        let now = SystemTime::now();
+        let mut credentials = AwsCredentials {
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            session_token: None,
+            expiration: None,
+        };
+        assert!(!credentials.expires_soon(now));
+        credentials.expiration = Some(now + Duration::from_secs(30));
+        assert!(credentials.expires_soon(now));
+        credentials.expiration = Some(now + Duration::from_secs(3600));
+        assert!(!credentials.expires_soon(now));
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(
+            parse_timestamp("1970-01-01T00:00:00Z"),
+            Some(SystemTime::UNIX_EPOCH)
+        );
+        assert_eq!(
+            parse_timestamp("2024-02-29T23:59:59.123+00:00"),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1709251199))
+        );
+        assert_eq!(parse_timestamp("2024-02-29"), None);
+        assert_eq!(parse_timestamp("2024-02-29T10:00:00+02:00"), None);
+    }
+}
@@ -0,0 +1,91 @@
+use digest_auth::AuthContext;
+use reqwest::{header, RequestBuilder, StatusCode};
+
+/// Sends the request and, if the server answers with a `WWW-Authenticate: Digest` challenge,
+/// retries it with the computed `Authorization` header
+pub async fn send(
+    request: RequestBuilder,
+    user: &str,
+    password: &str,
+) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    // Streamed bodies can't be sent twice
+    let mut retry = match request.try_clone() {
+        Some(retry) => retry,
+        None => return Ok(client.execute(request).await?),
+    };
+
+    let res = client.execute(request).await?;
+    let challenge = match res
+        .headers()
+        .get(header::WWW_AUTHENTICATE)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(challenge) if res.status() == StatusCode::UNAUTHORIZED => challenge,
+        _ => return Ok(res),
+    };
+
+    let url = retry.url();
+    let uri = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let authorization = authorization(
+        challenge,
+        AuthContext::new_with_method(
+            user,
+            password,
+            uri,
+            retry.body().and_then(|body| body.as_bytes()),
+            retry.method().as_str().into(),
+        ),
+    )?;
+    retry
+        .headers_mut()
+        .insert(header::AUTHORIZATION, authorization.parse()?);
+
+    Ok(client.execute(retry).await?)
+}
+
+/// Computes the `Authorization` header that answers `challenge`
+fn authorization(challenge: &str, context: AuthContext) -> Result<String, digest_auth::Error> {
+    let mut prompt = digest_auth::parse(challenge)?;
+    Ok(prompt.respond(&context)?.to_header_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use digest_auth::AuthContext;
+
+    use super::authorization;
+
+    #[test]
+    fn test_authorization() {
+        // Example of RFC 2617
+        let challenge = r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#;
+        let mut context = AuthContext::new("Mufasa", "Circle Of Life", "/dir/index.html");
+        context.set_custom_cnonce("0a4f113b");
+
+        let header = authorization(challenge, context).unwrap();
+        assert!(header.starts_with("Digest "));
+        for field in [
+            r#"username="Mufasa""#,
+            r#"realm="testrealm@host.com""#,
+            r#"uri="/dir/index.html""#,
+            "qop=auth",
+            "nc=00000001",
+            r#"cnonce="0a4f113b""#,
+            r#"response="6629fae49393a05397450978507c4ef1""#,
+            r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+        ] {
+            assert!(header.contains(field), "{} not in {}", field, header);
+        }
+    }
+
+    #[test]
+    fn test_authorization_invalid_challenge() {
+        let context = AuthContext::new("user", "pass", "/");
+        assert!(authorization("Basic realm=\"test\"", context).is_err());
+    }
+}
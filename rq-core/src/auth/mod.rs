@@ -0,0 +1,6 @@
+pub mod apikey;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod aws;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod digest;
+pub mod oauth2;
@@ -0,0 +1,153 @@
+/// Client credentials of `# @oauth2 token_url=<url> client_id=<id> client_secret=<secret> [scope=<scope>]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuth2 {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: Option<String>,
+}
+
+impl OAuth2 {
+    /// Parses the `key=value` pairs of the annotation. `token_url`, `client_id` and
+    /// `client_secret` are required
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut token_url = None;
+        let mut client_id = None;
+        let mut client_secret = None;
+        let mut scope = None;
+        for (key, value) in value.split_whitespace().filter_map(|p| p.split_once('=')) {
+            let value = Some(value.to_string());
+            match key {
+                "token_url" => token_url = value,
+                "client_id" => client_id = value,
+                "client_secret" => client_secret = value,
+                "scope" => scope = value,
+                _ => {}
+            }
+        }
+        Some(Self {
+            token_url: token_url?,
+            client_id: client_id?,
+            client_secret: client_secret?,
+            scope,
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use session::{token, TokenCache};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod session {
+    use std::{
+        collections::HashMap,
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    use serde::Deserialize;
+
+    use super::OAuth2;
+    use crate::{
+        parser::{BodyEncoder, HttpMethod, HttpRequest},
+        request::{execute, ClientConfig},
+    };
+
+    /// Access token and expiration by token url and client id
+    type Tokens = HashMap<(String, String), (String, Option<Instant>)>;
+
+    /// Access tokens of the session
+    #[derive(Debug, Default)]
+    pub struct TokenCache(Mutex<Tokens>);
+
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        access_token: String,
+        /// Lifetime in seconds. Tokens without one are kept for the session
+        expires_in: Option<u64>,
+    }
+
+    /// Access token for the client credentials, requested from the token endpoint
+    /// unless a cached one is still valid
+    pub async fn token(
+        oauth: &OAuth2,
+        config: &ClientConfig,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let key = (oauth.token_url.clone(), oauth.client_id.clone());
+        if let Some((token, expires)) = config.oauth2_tokens.0.lock().unwrap().get(&key) {
+            if expires.is_none_or(|expires| Instant::now() < expires) {
+                return Ok(token.clone());
+            }
+        }
+
+        // Built in its own scope since the serializer can't be held across the request
+        let body = {
+            let mut form = form_urlencoded::Serializer::new(String::new());
+            form.append_pair("grant_type", "client_credentials")
+                .append_pair("client_id", &oauth.client_id)
+                .append_pair("client_secret", &oauth.client_secret);
+            if let Some(scope) = &oauth.scope {
+                form.append_pair("scope", scope);
+            }
+            form.finish()
+        };
+        let req = HttpRequest::builder()
+            .method(HttpMethod::Post)
+            .url(oauth.token_url.as_str())
+            .header("Accept", "application/json")
+            .body(body)
+            .body_encoder(BodyEncoder::Form)
+            .build();
+        // Boxed since `execute` calls this function
+        let res = Box::pin(execute(&req, config)).await?;
+        if !res.status.is_success() {
+            return Err(format!(
+                "oauth2 token request to {} failed: {} {}",
+                oauth.token_url,
+                res.status_line(),
+                res.body.trim()
+            )
+            .into());
+        }
+        let TokenResponse {
+            access_token,
+            expires_in,
+        } = serde_json::from_str(&res.body).map_err(|e| {
+            format!(
+                "oauth2 token response of {} has no access_token: {}",
+                oauth.token_url, e
+            )
+        })?;
+
+        let expires = expires_in.map(|secs| Instant::now() + Duration::from_secs(secs));
+        config
+            .oauth2_tokens
+            .0
+            .lock()
+            .unwrap()
+            .insert(key, (access_token.clone(), expires));
+        Ok(access_token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OAuth2;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            OAuth2::parse("token_url=https://auth.dev/token client_id=rq client_secret=s3cr=t scope=read:users"),
+            Some(OAuth2 {
+                token_url: "https://auth.dev/token".to_string(),
+                client_id: "rq".to_string(),
+                client_secret: "s3cr=t".to_string(),
+                scope: Some("read:users".to_string()),
+            })
+        );
+        assert_eq!(
+            OAuth2::parse("token_url=https://auth.dev/token client_id=rq"),
+            None
+        );
+    }
+}
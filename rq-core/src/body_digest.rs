@@ -0,0 +1,56 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256, Sha512};
+
+/// Algorithm of the `Digest` header added by `# @digest-body`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Parses the annotation value, `SHA-256` when empty
+    pub fn parse(value: Option<&str>) -> Option<Self> {
+        match value.map(str::to_uppercase).as_deref() {
+            None | Some("") | Some("SHA-256") => Some(Self::Sha256),
+            Some("SHA-512") => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+}
+
+/// Value of the `Digest` header of `body`, e.g. `SHA-256=<base64>`
+pub fn digest_header(algorithm: DigestAlgorithm, body: &[u8]) -> String {
+    match algorithm {
+        DigestAlgorithm::Sha256 => format!("SHA-256={}", STANDARD.encode(Sha256::digest(body))),
+        DigestAlgorithm::Sha512 => format!("SHA-512={}", STANDARD.encode(Sha512::digest(body))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{digest_header, DigestAlgorithm};
+
+    #[test]
+    fn test_digest_header() {
+        assert_eq!(
+            digest_header(DigestAlgorithm::Sha256, b"{\"hello\": \"world\"}"),
+            "SHA-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE="
+        );
+        assert_eq!(
+            digest_header(DigestAlgorithm::Sha512, b""),
+            "SHA-512=z4PhNX7vuL3xVChQ1m2AB9Yg5AULVxXcg/SpIdNs6c5H0NE8XYXysP+DGNKHfuwvY7kxvUdBeoGlODJ6+SfaPg=="
+        );
+    }
+
+    #[test]
+    fn test_parse_algorithm() {
+        assert_eq!(DigestAlgorithm::parse(None), Some(DigestAlgorithm::Sha256));
+        assert_eq!(
+            DigestAlgorithm::parse(Some("sha-512")),
+            Some(DigestAlgorithm::Sha512)
+        );
+        assert_eq!(DigestAlgorithm::parse(Some("MD5")), None);
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::parser::{BodyEncoder, HttpMethod, HttpRequest};
+
+/// Builds a request without going through the parser, see [`HttpRequest::builder`]
+#[derive(Debug, Clone)]
+pub struct HttpRequestBuilder {
+    req: HttpRequest,
+}
+
+impl HttpRequest {
+    /// Starts a `GET` request with version `1.1`
+    pub fn builder() -> HttpRequestBuilder {
+        HttpRequestBuilder {
+            req: HttpRequest {
+                version: "1.1".to_string(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl HttpRequestBuilder {
+    pub fn method(mut self, method: HttpMethod) -> Self {
+        self.req.method = method;
+        self
+    }
+
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.req.url = url.into();
+        self
+    }
+
+    /// Version without the `HTTP/` prefix, e.g. `1.0`
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.req.version = version.into();
+        self
+    }
+
+    /// Adds a header, replacing a previous one with the same name
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.req.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn headers<K, V>(mut self, headers: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.req
+            .headers
+            .extend(headers.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Appends a parameter to the query string, encoded when the request is sent
+    pub fn query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.req.query.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.req.body = body.into();
+        self
+    }
+
+    pub fn body_encoder(mut self, encoder: BodyEncoder) -> Self {
+        self.req.body_encoder = encoder;
+        self
+    }
+
+    pub fn build(self) -> HttpRequest {
+        self.req
+    }
+}
+
+/// Collects `(name, value)` pairs into request headers
+pub fn headers<K, V>(pairs: impl IntoIterator<Item = (K, V)>) -> HashMap<String, String>
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    pairs
+        .into_iter()
+        .map(|(k, v)| (k.into(), v.into()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::headers;
+    use crate::parser::{parse, BodyEncoder, HttpMethod, HttpRequest};
+
+    #[test]
+    fn test_builder() {
+        let req = HttpRequest::builder()
+            .method(HttpMethod::Post)
+            .url("https://api.dev/users")
+            .header("Content-Type", "application/json")
+            .body(r#"{ "name": "rq" }"#)
+            .build();
+        assert_eq!(
+            req.to_string(),
+            "POST https://api.dev/users HTTP/1.1 [Content-Type: application/json]"
+        );
+        assert_eq!(req.body, r#"{ "name": "rq" }"#);
+
+        let parsed = &parse(
+            "POST https://api.dev/users HTTP/1.1\nContent-Type: application/json\n\n{ \"name\": \"rq\" }\n\n",
+        )
+        .unwrap()
+        .requests[0];
+        assert_eq!(req.to_string(), parsed.to_string());
+        assert_eq!(req.body, parsed.body);
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let req = HttpRequest::builder().url("/health").build();
+        assert_eq!(req.to_string(), "GET /health HTTP/1.1");
+        assert_eq!(req.body_encoder, BodyEncoder::Raw);
+
+        let req = HttpRequest::builder()
+            .url("/search")
+            .version("1.0")
+            .query("q", "a b")
+            .headers(headers([("Accept", "*/*")]))
+            .body_encoder(BodyEncoder::Form)
+            .build();
+        assert_eq!(req.full_url(), "/search?q=a+b");
+        assert_eq!(req.to_string(), "GET /search HTTP/1.0 [Accept: */*]");
+        assert_eq!(req.body_encoder, BodyEncoder::Form);
+    }
+}
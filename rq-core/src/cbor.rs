@@ -0,0 +1,84 @@
+use ciborium::value::Value as Cbor;
+use serde_json::{Map, Value};
+
+/// Decodes a CBOR body into pretty printed JSON.
+/// Returns `None` if `bytes` are not valid CBOR.
+pub fn cbor_to_json(bytes: &[u8]) -> Option<String> {
+    let value: Cbor = ciborium::de::from_reader(bytes).ok()?;
+    serde_json::to_string_pretty(&to_json(value)).ok()
+}
+
+/// CBOR values without a JSON equivalent are approximated: byte strings become
+/// hex strings, non-string map keys are stringified and tags are dropped
+fn to_json(value: Cbor) -> Value {
+    match value {
+        Cbor::Integer(i) => {
+            let i = i128::from(i);
+            i64::try_from(i)
+                .map(Value::from)
+                .or_else(|_| u64::try_from(i).map(Value::from))
+                .unwrap_or_else(|_| Value::String(i.to_string()))
+        }
+        Cbor::Bytes(bytes) => Value::String(bytes.iter().map(|b| format!("{:02x}", b)).collect()),
+        Cbor::Float(f) => Value::from(f),
+        Cbor::Text(s) => Value::String(s),
+        Cbor::Bool(b) => Value::Bool(b),
+        Cbor::Null => Value::Null,
+        Cbor::Tag(_, value) => to_json(*value),
+        Cbor::Array(values) => Value::Array(values.into_iter().map(to_json).collect()),
+        Cbor::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| {
+                    let key = match to_json(k) {
+                        Value::String(s) => s,
+                        key => key.to_string(),
+                    };
+                    (key, to_json(v))
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ciborium::value::Value as Cbor;
+
+    use super::cbor_to_json;
+
+    fn encode(value: &Cbor) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(value, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_cbor_to_json() {
+        let value = Cbor::Map(vec![
+            (Cbor::Text("id".into()), Cbor::Integer(7.into())),
+            (
+                Cbor::Text("tags".into()),
+                Cbor::Array(vec![Cbor::Text("a".into()), Cbor::Bool(true)]),
+            ),
+            (Cbor::Integer(1.into()), Cbor::Bytes(vec![0xde, 0xad])),
+        ]);
+        assert_eq!(
+            cbor_to_json(&encode(&value)).unwrap(),
+            r#"{
+  "id": 7,
+  "tags": [
+    "a",
+    true
+  ],
+  "1": "dead"
+}"#
+        );
+    }
+
+    #[test]
+    fn test_invalid_cbor() {
+        assert_eq!(cbor_to_json(&[0xff, 0x00]), None);
+    }
+}
@@ -0,0 +1,87 @@
+use std::{fmt::Display, io::Read};
+
+/// Size of a response body before and after decompression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compression {
+    /// Value of the `Content-Encoding` header
+    pub encoding: String,
+    pub compressed: usize,
+    pub decompressed: usize,
+}
+
+impl Compression {
+    pub fn ratio(&self) -> f64 {
+        self.decompressed as f64 / self.compressed.max(1) as f64
+    }
+}
+
+/// Summary of whether the server compressed the response, e.g. `server compressed: yes (gzip, 3.1x)`
+pub struct CompressionReport<'a>(pub Option<&'a Compression>);
+
+impl Display for CompressionReport<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(c) => write!(
+                f,
+                "server compressed: yes ({}, {:.1}x)",
+                c.encoding,
+                c.ratio()
+            ),
+            None => write!(f, "server compressed: no"),
+        }
+    }
+}
+
+/// Decompresses a body with the given `Content-Encoding`: `gzip`, `deflate` or `br`.
+/// Returns `None` for unknown encodings or invalid data.
+pub fn decompress(encoding: &str, bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut ret = Vec::new();
+    let read = match encoding.trim().to_lowercase().as_str() {
+        "gzip" | "x-gzip" => flate2::read::GzDecoder::new(bytes).read_to_end(&mut ret),
+        // `deflate` should be zlib wrapped, but some servers send raw deflate
+        "deflate" => flate2::read::ZlibDecoder::new(bytes)
+            .read_to_end(&mut ret)
+            .or_else(|_| {
+                ret.clear();
+                flate2::read::DeflateDecoder::new(bytes).read_to_end(&mut ret)
+            }),
+        "br" => brotli::Decompressor::new(bytes, 4096).read_to_end(&mut ret),
+        _ => return None,
+    };
+    read.ok().map(|_| ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{decompress, Compression, CompressionReport};
+
+    #[test]
+    fn test_decompress_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"hello hello hello").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(
+            decompress("gzip", &compressed).as_deref(),
+            Some(b"hello hello hello".as_ref())
+        );
+        assert_eq!(decompress("gzip", b"not gzip"), None);
+        assert_eq!(decompress("zstd", &compressed), None);
+    }
+
+    #[test]
+    fn test_compression_report() {
+        let compression = Compression {
+            encoding: "gzip".to_string(),
+            compressed: 100,
+            decompressed: 310,
+        };
+        assert_eq!(
+            CompressionReport(Some(&compression)).to_string(),
+            "server compressed: yes (gzip, 3.1x)"
+        );
+        assert_eq!(CompressionReport(None).to_string(), "server compressed: no");
+    }
+}
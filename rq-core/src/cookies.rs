@@ -0,0 +1,164 @@
+use reqwest::Url;
+
+pub use reqwest::cookie::Jar;
+
+/// Cookie read from a cookie file
+#[derive(Debug, PartialEq, Eq)]
+pub struct FileCookie {
+    pub domain: String,
+    pub path: String,
+    pub secure: bool,
+    pub name: String,
+    pub value: String,
+}
+
+/// Parses a Netscape `cookies.txt` file, as exported by browsers and curl, or a file of
+/// `name=value; domain=...; path=...` lines. Malformed lines are skipped and reported
+/// as warnings.
+pub fn parse_cookie_file(content: &str) -> (Vec<FileCookie>, Vec<String>) {
+    let mut cookies = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        // curl marks http-only cookies with this prefix, any other `#` starts a comment
+        let line = line.trim().trim_start_matches("#HttpOnly_");
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let cookie = if line.contains('\t') {
+            parse_netscape_line(line)
+        } else {
+            parse_simple_line(line)
+        };
+        match cookie {
+            Some(cookie) => cookies.push(cookie),
+            None => warnings.push(format!("skipping malformed cookie on line {}", i + 1)),
+        }
+    }
+
+    (cookies, warnings)
+}
+
+/// `domain include_subdomains path secure expiry name value`, separated by tabs
+fn parse_netscape_line(line: &str) -> Option<FileCookie> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [domain, _, path, secure, _, name, value] = fields[..] else {
+        return None;
+    };
+    if domain.is_empty() || name.is_empty() {
+        return None;
+    }
+    Some(FileCookie {
+        domain: domain.trim_start_matches('.').to_string(),
+        path: path.to_string(),
+        secure: secure.eq_ignore_ascii_case("TRUE"),
+        name: name.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// `name=value; domain=example.com; path=/; secure`
+fn parse_simple_line(line: &str) -> Option<FileCookie> {
+    let mut attributes = line.split(';').map(str::trim);
+    let (name, value) = attributes.next()?.split_once('=')?;
+    let mut cookie = FileCookie {
+        domain: String::new(),
+        path: "/".to_string(),
+        secure: false,
+        name: name.trim().to_string(),
+        value: value.trim().to_string(),
+    };
+    for attribute in attributes {
+        match attribute.split_once('=') {
+            Some((key, value)) if key.eq_ignore_ascii_case("domain") => {
+                cookie.domain = value.trim_start_matches('.').to_string()
+            }
+            Some((key, value)) if key.eq_ignore_ascii_case("path") => {
+                cookie.path = value.to_string()
+            }
+            None if attribute.eq_ignore_ascii_case("secure") => cookie.secure = true,
+            _ => {}
+        }
+    }
+    (!cookie.domain.is_empty() && !cookie.name.is_empty()).then_some(cookie)
+}
+
+/// Adds the cookies to `jar`, as if they were set by their domain
+pub fn seed_jar(jar: &Jar, cookies: &[FileCookie]) {
+    for cookie in cookies {
+        let scheme = if cookie.secure { "https" } else { "http" };
+        let Ok(url) = Url::parse(&format!("{}://{}{}", scheme, cookie.domain, cookie.path)) else {
+            continue;
+        };
+        let mut set_cookie = format!(
+            "{}={}; Domain={}; Path={}",
+            cookie.name, cookie.value, cookie.domain, cookie.path
+        );
+        if cookie.secure {
+            set_cookie.push_str("; Secure");
+        }
+        jar.add_cookie_str(&set_cookie, &url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{
+        cookie::{CookieStore, Jar},
+        Url,
+    };
+
+    use super::{parse_cookie_file, seed_jar};
+
+    const COOKIES: &str = "# Netscape HTTP Cookie File
+.example.com\tTRUE\t/\tFALSE\t0\tsession\tabc123
+#HttpOnly_example.com\tFALSE\t/api\tTRUE\t0\ttoken\tsecret
+theme=dark; domain=example.com; path=/
+not a cookie
+example.com\tFALSE\t/
+";
+
+    #[test]
+    fn test_parse_cookie_file() {
+        let (cookies, warnings) = parse_cookie_file(COOKIES);
+        assert_eq!(cookies.len(), 3);
+        assert_eq!(cookies[0].domain, "example.com");
+        assert_eq!(cookies[0].name, "session");
+        assert!(cookies[1].secure);
+        assert_eq!(cookies[1].path, "/api");
+        assert_eq!(cookies[2].value, "dark");
+        assert_eq!(
+            warnings,
+            vec![
+                "skipping malformed cookie on line 5",
+                "skipping malformed cookie on line 6"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_seed_jar() {
+        let (cookies, _) = parse_cookie_file(COOKIES);
+        let jar = Jar::default();
+        seed_jar(&jar, &cookies);
+
+        // Sorted names of the cookies sent to `url`
+        let sent = |url: &str| {
+            let header = jar.cookies(&Url::parse(url).unwrap());
+            let mut names: Vec<String> = header
+                .iter()
+                .flat_map(|v| v.to_str().unwrap().split("; "))
+                .map(|cookie| cookie.split('=').next().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(
+            sent("https://example.com/api/users"),
+            vec!["session", "theme", "token"]
+        );
+        // `token` is secure and restricted to `/api`
+        assert_eq!(sent("http://www.example.com/"), vec!["session", "theme"]);
+        assert!(sent("https://other.dev/").is_empty());
+    }
+}
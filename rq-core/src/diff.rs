@@ -0,0 +1,81 @@
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffChunk {
+    /// Bytes present in both inputs
+    Equal(Vec<u8>),
+    /// Bytes only present in the old input
+    Delete(Vec<u8>),
+    /// Bytes only present in the new input
+    Insert(Vec<u8>),
+}
+
+/// Diffs two byte slices. Replaced bytes are reported as a `Delete` followed by an `Insert`
+pub fn binary_diff(a: &[u8], b: &[u8]) -> Vec<DiffChunk> {
+    let mut chunks = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, a, b) {
+        match op {
+            DiffOp::Equal { old_index, len, .. } => {
+                chunks.push(DiffChunk::Equal(a[old_index..old_index + len].to_vec()));
+            }
+            DiffOp::Delete {
+                old_index, old_len, ..
+            } => {
+                chunks.push(DiffChunk::Delete(
+                    a[old_index..old_index + old_len].to_vec(),
+                ));
+            }
+            DiffOp::Insert {
+                new_index, new_len, ..
+            } => {
+                chunks.push(DiffChunk::Insert(
+                    b[new_index..new_index + new_len].to_vec(),
+                ));
+            }
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => {
+                chunks.push(DiffChunk::Delete(
+                    a[old_index..old_index + old_len].to_vec(),
+                ));
+                chunks.push(DiffChunk::Insert(
+                    b[new_index..new_index + new_len].to_vec(),
+                ));
+            }
+        }
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{binary_diff, DiffChunk};
+
+    #[test]
+    fn test_binary_diff() {
+        let a = [0x89, 0x50, 0x4E, 0x47, 0x00, 0x01];
+        let b = [0x89, 0x50, 0xFF, 0x47, 0x00, 0x01, 0x02];
+        assert_eq!(
+            binary_diff(&a, &b),
+            vec![
+                DiffChunk::Equal(vec![0x89, 0x50]),
+                DiffChunk::Delete(vec![0x4E]),
+                DiffChunk::Insert(vec![0xFF]),
+                DiffChunk::Equal(vec![0x47, 0x00, 0x01]),
+                DiffChunk::Insert(vec![0x02]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binary_diff_equal() {
+        assert_eq!(
+            binary_diff(&[1, 2, 3], &[1, 2, 3]),
+            vec![DiffChunk::Equal(vec![1, 2, 3])]
+        );
+        assert!(binary_diff(&[], &[]).is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+
+use std::borrow::Cow;
+
+/// Decodes `bytes` using the charset of `content_type`, UTF-8 by default.
+/// Returns `None` when `bytes` are not valid in that encoding, i.e. binary content.
+pub fn decode_with_encoding(bytes: &[u8], content_type: Option<&str>) -> Option<String> {
+    let encoding = content_type
+        .and_then(|ct| {
+            ct.split(';')
+                .filter_map(|param| param.trim().split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+        })
+        .and_then(|(_, charset)| Encoding::for_label(charset.trim_matches('"').as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, _, malformed) = encoding.decode(bytes);
+    if malformed {
+        None
+    } else {
+        Some(text.into_owned())
+    }
+}
+
+/// Text encoded with [`encode_with_encoding`]
+#[derive(Debug, PartialEq, Eq)]
+pub struct EncodedText {
+    pub bytes: Vec<u8>,
+    /// Name of the encoding used
+    pub encoding: &'static str,
+    /// Whether some characters couldn't be represented in the target encoding.
+    /// They are replaced by HTML numeric character references
+    pub unmappable: bool,
+}
+
+/// Encodes `text` using the encoding identified by `label`, e.g. `latin1` or `shift_jis`.
+/// Returns `None` if the label is not a known encoding.
+pub fn encode_with_encoding(text: &str, label: &str) -> Option<EncodedText> {
+    let encoding = Encoding::for_label(label.trim().as_bytes())?;
+    let (bytes, used, unmappable) = encoding.encode(text);
+    Some(EncodedText {
+        bytes: bytes.into_owned(),
+        encoding: used.name(),
+        unmappable,
+    })
+}
+
+/// Line endings of saved text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// Keep the line endings of the response
+    #[default]
+    Preserve,
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    /// Converts every `\r\n` and `\n` line ending of `text`. Lone `\r` are left as is
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let lf = || text.replace("\r\n", "\n");
+        match self {
+            Self::Preserve => Cow::Borrowed(text),
+            Self::Lf if !text.contains("\r\n") => Cow::Borrowed(text),
+            Self::Lf => Cow::Owned(lf()),
+            Self::Crlf => Cow::Owned(lf().replace('\n', "\r\n")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_with_encoding, encode_with_encoding, LineEnding};
+
+    #[test]
+    fn test_line_ending() {
+        let mixed = "a\r\nb\nc\rd\r\n";
+        assert_eq!(LineEnding::Preserve.apply(mixed), mixed);
+        assert_eq!(LineEnding::Lf.apply(mixed), "a\nb\nc\rd\n");
+        assert_eq!(LineEnding::Crlf.apply(mixed), "a\r\nb\r\nc\rd\r\n");
+        assert_eq!(LineEnding::Crlf.apply("no newline"), "no newline");
+    }
+
+    #[test]
+    fn test_decode_with_encoding() {
+        assert_eq!(
+            decode_with_encoding("café".as_bytes(), None).as_deref(),
+            Some("café")
+        );
+        assert_eq!(
+            decode_with_encoding(
+                &[0x63, 0x61, 0x66, 0xE9],
+                Some("text/plain; charset=latin1")
+            )
+            .as_deref(),
+            Some("café")
+        );
+        assert_eq!(
+            decode_with_encoding(&[0x89, 0x50, 0x4E, 0x47], Some("image/png")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_encode_latin1() {
+        let encoded = encode_with_encoding("café", "latin1").unwrap();
+        assert_eq!(encoded.bytes, vec![0x63, 0x61, 0x66, 0xE9]);
+        assert_eq!(encoded.encoding, "windows-1252");
+        assert!(!encoded.unmappable);
+    }
+
+    #[test]
+    fn test_encode_shift_jis() {
+        let encoded = encode_with_encoding("日本", "shift_jis").unwrap();
+        assert_eq!(encoded.bytes, vec![0x93, 0xFA, 0x96, 0x7B]);
+        assert!(!encoded.unmappable);
+    }
+
+    #[test]
+    fn test_encode_unmappable() {
+        let encoded = encode_with_encoding("a日", "latin1").unwrap();
+        assert_eq!(encoded.bytes, b"a&#26085;".to_vec());
+        assert!(encoded.unmappable);
+    }
+
+    #[test]
+    fn test_encode_unknown_label() {
+        assert!(encode_with_encoding("text", "not-an-encoding").is_none());
+    }
+}
@@ -0,0 +1,92 @@
+use serde::Deserialize;
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::parser::HttpRequest;
+
+/// Defaults applied to every request while the environment is active
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Environment {
+    /// Prepended to relative request urls
+    pub base: Option<String>,
+    pub headers: HashMap<String, String>,
+}
+
+/// Environments by name, as defined in the env JSON file:
+/// `{ "dev": { "base": "http://localhost:8080", "headers": { "X-Env": "dev" } } }`
+pub type Environments = BTreeMap<String, Environment>;
+
+pub fn parse_environments(json: &str) -> Result<Environments, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+impl Environment {
+    /// Applies the environment defaults to `req`.
+    /// Request headers take precedence over the environment ones.
+    pub fn resolve(&self, req: &HttpRequest) -> HttpRequest {
+        let mut ret = req.clone();
+
+        if let Some(base) = &self.base {
+            if !ret.url.contains("://") {
+                ret.url = format!(
+                    "{}/{}",
+                    base.trim_end_matches('/'),
+                    ret.url.trim_start_matches('/')
+                );
+            }
+        }
+
+        for (name, value) in &self.headers {
+            if !ret.has_header(name) {
+                ret.headers.insert(name.clone(), value.clone());
+            }
+        }
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_environments;
+    use crate::parser::parse;
+
+    const ENVIRONMENTS: &str = r#"{
+        "dev": { "base": "http://localhost:8080/", "headers": { "X-Env": "dev" } },
+        "prod": { "base": "https://api.example.com", "headers": { "X-Env": "prod", "Accept": "text/plain" } },
+        "empty": {}
+    }"#;
+
+    #[test]
+    fn test_switch_environment() {
+        let environments = parse_environments(ENVIRONMENTS).unwrap();
+        let file = parse("GET /users HTTP/1.1\nAccept: application/json\n\n").unwrap();
+        let req = &file.requests[0];
+
+        let dev = environments["dev"].resolve(req);
+        assert_eq!(dev.url, "http://localhost:8080/users");
+        assert_eq!(dev.headers["X-Env"], "dev");
+
+        let prod = environments["prod"].resolve(req);
+        assert_eq!(prod.url, "https://api.example.com/users");
+        assert_eq!(prod.headers["X-Env"], "prod");
+        // Request headers take precedence
+        assert_eq!(prod.headers["Accept"], "application/json");
+
+        let empty = environments["empty"].resolve(req);
+        assert_eq!(empty.url, "/users");
+        assert!(!empty.headers.contains_key("X-Env"));
+    }
+
+    #[test]
+    fn test_absolute_url_ignores_base() {
+        let environments = parse_environments(ENVIRONMENTS).unwrap();
+        let file = parse("GET https://other.dev/users HTTP/1.1\nx-env: local\n\n").unwrap();
+
+        let req = environments["prod"].resolve(&file.requests[0]);
+        assert_eq!(req.url, "https://other.dev/users");
+        assert_eq!(req.headers["x-env"], "local");
+        assert!(!req.headers.contains_key("X-Env"));
+    }
+}
@@ -0,0 +1,304 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    StatusCode, Version,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{fmt::Display, time::Duration};
+
+use crate::{
+    encoding::decode_with_encoding,
+    parser::{HttpMethod, HttpRequest},
+    request::Response,
+};
+
+/// Version of the log format, increased on incompatible changes
+pub const LOG_VERSION: u64 = 1;
+
+/// Executed exchanges of a session, written as JSON
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExchangeLog {
+    pub version: u64,
+    pub exchanges: Vec<Exchange>,
+}
+
+impl Default for ExchangeLog {
+    fn default() -> Self {
+        Self {
+            version: LOG_VERSION,
+            exchanges: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub request: LoggedRequest,
+    pub response: LoggedResponse,
+    pub elapsed_ms: u64,
+}
+
+/// Request as it was sent, with its environment and variables resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedRequest {
+    pub method: String,
+    pub url: String,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedResponse {
+    /// e.g. `HTTP/1.1`
+    pub version: String,
+    pub status: u16,
+    /// Non-canonical reason phrase, see [`Response::reason`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub headers: Vec<(String, String)>,
+    /// Text body, `None` for binary bodies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_base64: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum LogError {
+    Json(serde_json::Error),
+    UnsupportedVersion(u64),
+    Invalid(String),
+}
+
+impl Display for LogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogError::Json(e) => write!(f, "invalid exchange log: {}", e),
+            LogError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported exchange log version {} (expected {})",
+                version, LOG_VERSION
+            ),
+            LogError::Invalid(e) => write!(f, "invalid exchange: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LogError {}
+
+impl From<serde_json::Error> for LogError {
+    fn from(e: serde_json::Error) -> Self {
+        LogError::Json(e)
+    }
+}
+
+impl ExchangeLog {
+    /// Parses a log, checking its version before the exchanges
+    pub fn parse(json: &str) -> Result<Self, LogError> {
+        #[derive(Deserialize)]
+        struct Header {
+            version: u64,
+        }
+
+        let header: Header = serde_json::from_str(json)?;
+        if header.version != LOG_VERSION {
+            return Err(LogError::UnsupportedVersion(header.version));
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, LogError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(k, v)| {
+            (
+                k.to_string(),
+                String::from_utf8_lossy(v.as_bytes()).to_string(),
+            )
+        })
+        .collect()
+}
+
+impl Exchange {
+    pub fn new(req: &HttpRequest, res: &Response, elapsed: Duration) -> Self {
+        let mut headers: Vec<_> = req
+            .headers
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        headers.sort();
+
+        Self {
+            request: LoggedRequest {
+                method: req.method.to_string(),
+                url: req.full_url(),
+                version: req.version.clone(),
+                headers,
+                body: req.body.clone(),
+            },
+            response: LoggedResponse {
+                version: format!("{:?}", res.version),
+                status: res.status.as_u16(),
+                reason: res.reason.clone(),
+                headers: header_pairs(&res.headers),
+                body: (!res.binary).then(|| res.body.clone()),
+                body_base64: res.binary.then(|| STANDARD.encode(&res.bytes)),
+            },
+            elapsed_ms: elapsed.as_millis() as u64,
+        }
+    }
+
+    pub fn request(&self) -> Result<HttpRequest, LogError> {
+        let method = HttpMethod::iterator()
+            .find(|m| m.to_string() == self.request.method)
+            .ok_or_else(|| LogError::Invalid(format!("method {}", self.request.method)))?;
+
+        Ok(HttpRequest::builder()
+            .method(method.clone())
+            .url(&self.request.url)
+            .version(&self.request.version)
+            .headers(self.request.headers.iter().cloned())
+            .body(&self.request.body)
+            .build())
+    }
+
+    pub fn response(&self) -> Result<Response, LogError> {
+        let res = &self.response;
+        let version = match res.version.as_str() {
+            "HTTP/0.9" => Version::HTTP_09,
+            "HTTP/1.0" => Version::HTTP_10,
+            "HTTP/1.1" => Version::HTTP_11,
+            "HTTP/2.0" => Version::HTTP_2,
+            "HTTP/3.0" => Version::HTTP_3,
+            version => return Err(LogError::Invalid(format!("version {}", version))),
+        };
+        let status = StatusCode::from_u16(res.status)
+            .map_err(|_| LogError::Invalid(format!("status {}", res.status)))?;
+
+        let mut headers = HeaderMap::new();
+        for (k, v) in &res.headers {
+            let name = HeaderName::from_bytes(k.as_bytes())
+                .map_err(|_| LogError::Invalid(format!("header {}", k)))?;
+            let value = HeaderValue::from_str(v)
+                .map_err(|_| LogError::Invalid(format!("header value {}", v)))?;
+            headers.append(name, value);
+        }
+
+        let bytes = match (&res.body, &res.body_base64) {
+            (_, Some(encoded)) => Bytes::from(
+                STANDARD
+                    .decode(encoded)
+                    .map_err(|e| LogError::Invalid(e.to_string()))?,
+            ),
+            (Some(body), None) => Bytes::from(body.clone()),
+            (None, None) => Bytes::new(),
+        };
+        let content_type = headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok());
+        let (body, binary) = match &res.body {
+            Some(body) => (body.clone(), false),
+            None => match decode_with_encoding(&bytes, content_type) {
+                Some(body) => (body, false),
+                None => (String::from_utf8_lossy(&bytes).to_string(), true),
+            },
+        };
+
+        Ok(Response {
+            version,
+            status,
+            reason: self.response.reason.clone(),
+            headers,
+            body,
+            bytes,
+            binary,
+            elapsed: Duration::from_millis(self.elapsed_ms),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Exchange, ExchangeLog, LogError};
+    use crate::{
+        parser::parse,
+        request::{execute, tests::serve, ClientConfig},
+    };
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let (url, _rx) = serve(
+            "Content-Type: application/json\r\nX-Request-Id: 42\r\n",
+            br#"{"id": 1}"#.to_vec(),
+        );
+        let file = parse(&format!(
+            "POST {}/users HTTP/1.1\nX-Env: dev\n\n{{\"name\": \"rq\"}}",
+            url
+        ))
+        .unwrap();
+        let req = &file.requests[0];
+        let res = execute(req, &ClientConfig::default()).await.unwrap();
+
+        let mut log = ExchangeLog::default();
+        log.exchanges
+            .push(Exchange::new(req, &res, Duration::from_millis(12)));
+        let log = ExchangeLog::parse(&log.to_json().unwrap()).unwrap();
+
+        let exchange = &log.exchanges[0];
+        assert_eq!(exchange.elapsed_ms, 12);
+
+        let logged_req = exchange.request().unwrap();
+        assert_eq!(logged_req.method.to_string(), "POST");
+        assert_eq!(logged_req.url, req.url);
+        assert_eq!(logged_req.headers, req.headers);
+        assert_eq!(logged_req.body, req.body);
+
+        let logged_res = exchange.response().unwrap();
+        assert_eq!(logged_res.version, res.version);
+        assert_eq!(logged_res.status, res.status);
+        assert_eq!(logged_res.headers, res.headers);
+        assert_eq!(logged_res.body, res.body);
+        assert_eq!(logged_res.bytes, res.bytes);
+        assert!(!logged_res.binary);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_binary() {
+        let (url, _rx) = serve("Content-Type: image/png\r\n", vec![0x89, 0x50, 0x4E, 0x47]);
+        let file = parse(&format!("GET {}/logo.png HTTP/1.1\n\n", url)).unwrap();
+        let req = &file.requests[0];
+        let res = execute(req, &ClientConfig::default()).await.unwrap();
+        assert!(res.binary);
+
+        let mut log = ExchangeLog::default();
+        log.exchanges.push(Exchange::new(req, &res, Duration::ZERO));
+        let log = ExchangeLog::parse(&log.to_json().unwrap()).unwrap();
+
+        let logged_res = log.exchanges[0].response().unwrap();
+        assert_eq!(logged_res.bytes, res.bytes);
+        assert!(logged_res.binary);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        assert!(matches!(
+            ExchangeLog::parse(r#"{ "version": 2, "exchanges": [{ "new": "format" }] }"#),
+            Err(LogError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            ExchangeLog::parse(r#"{ "exchanges": [] }"#),
+            Err(LogError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+use super::shell_quote;
+use crate::{
+    parser::{BodyEncoder, HttpMethod, HttpRequest},
+    request::form_pairs,
+};
+
+impl HttpRequest {
+    /// Formats the request as a curl command line, e.g.
+    /// `curl -X POST -H 'Content-Type: application/json' --data-raw '{"id": 1}' https://api.dev/users`.
+    /// `GET` is curl's default method and is left out
+    pub fn to_curl(&self) -> String {
+        let mut args = vec!["curl".to_string()];
+        match self.method {
+            HttpMethod::Get => {}
+            // `-X HEAD` waits for a body that never comes
+            HttpMethod::Head => args.push("--head".to_string()),
+            _ => args.push(format!("-X {}", self.method)),
+        }
+        match self.version.as_str() {
+            "1.0" => args.push("--http1.0".to_string()),
+            "1.1" => args.push("--http1.1".to_string()),
+            "2" | "2.0" => args.push("--http2".to_string()),
+            _ => {}
+        }
+        if let Some((user, pass)) = &self.digest_auth {
+            args.push("--digest".to_string());
+            args.push(format!("-u {}", shell_quote(&format!("{}:{}", user, pass))));
+        } else if let Some((user, pass)) = &self.basic_auth {
+            args.push(format!("-u {}", shell_quote(&format!("{}:{}", user, pass))));
+        }
+
+        let mut headers: Vec<_> = self.headers.iter().collect();
+        headers.sort();
+        for (name, value) in headers {
+            let header = if value.is_empty() {
+                // `Name:` would remove the header
+                format!("{};", name)
+            } else {
+                format!("{}: {}", name, value)
+            };
+            args.push(format!("-H {}", shell_quote(&header)));
+        }
+
+        if let Some(path) = &self.body_file {
+            let path = format!("@{}", path.display());
+            args.push(format!("--data-binary {}", shell_quote(&path)));
+        } else if !self.body.is_empty() {
+            match self.body_encoder {
+                // `--data-raw` doesn't read `@file` references
+                BodyEncoder::Raw => args.push(format!("--data-raw {}", shell_quote(&self.body))),
+                BodyEncoder::NoEncode => {
+                    args.push(format!("--data-binary {}", shell_quote(&self.body)))
+                }
+                BodyEncoder::Form => args.extend(form_pairs(&self.body).iter().map(|(k, v)| {
+                    format!("--data-urlencode {}", shell_quote(&format!("{}={}", k, v)))
+                })),
+                // `--form-string` doesn't read `@file` and `<file` values
+                BodyEncoder::Multipart => {
+                    args.extend(form_pairs(&self.body).iter().map(|(k, v)| {
+                        format!("--form-string {}", shell_quote(&format!("{}={}", k, v)))
+                    }))
+                }
+            }
+        }
+
+        args.push(shell_quote(&self.full_url()).into_owned());
+        args.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+
+    fn curl(input: &str) -> String {
+        parse(input).unwrap().requests[0].to_curl()
+    }
+
+    #[test]
+    fn test_get() {
+        assert_eq!(curl("GET https://api.dev\n\n"), "curl https://api.dev");
+        assert_eq!(
+            curl("GET https://api.dev/users?page=2\nAccept: */*\n\n"),
+            "curl -H 'Accept: */*' 'https://api.dev/users?page=2'"
+        );
+        assert_eq!(
+            curl("HEAD https://bob:pw@api.dev HTTP/1.1\n\n"),
+            "curl --head --http1.1 -u bob:pw https://api.dev"
+        );
+    }
+
+    #[test]
+    fn test_headers() {
+        assert_eq!(
+            curl("DELETE https://api.dev/users/1\nX-Request-Id: 7\nAuthorization: Bearer a\"b\nAccept: */*\n\n"),
+            r#"curl -X DELETE -H 'Accept: */*' -H 'Authorization: Bearer a"b' -H 'X-Request-Id: 7' https://api.dev/users/1"#
+        );
+    }
+
+    #[test]
+    fn test_post_json() {
+        assert_eq!(
+            curl(
+                r#"POST https://api.dev/users HTTP/1.1
+Content-Type: application/json
+
+{"name": "rq's cli"}
+"#
+            ),
+            r#"curl -X POST --http1.1 -H 'Content-Type: application/json' --data-raw '{"name": "rq'\''s cli"}' https://api.dev/users"#
+        );
+    }
+
+    #[test]
+    fn test_encoded_bodies() {
+        assert_eq!(
+            curl("# @form\nPOST https://api.dev/login\n\nuser=rq&pass=a%20b\n"),
+            "curl -X POST --data-urlencode user=rq --data-urlencode 'pass=a b' https://api.dev/login"
+        );
+        assert_eq!(
+            curl("# @multipart\nPOST https://api.dev/upload\n\nname=@rq\n"),
+            "curl -X POST --form-string name=@rq https://api.dev/upload"
+        );
+        assert_eq!(
+            curl("PUT https://api.dev/users/1\n\n< ./user 1.json\n"),
+            "curl -X PUT --data-binary '@./user 1.json' https://api.dev/users/1"
+        );
+    }
+}
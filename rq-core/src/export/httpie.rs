@@ -0,0 +1,147 @@
+use serde_json::{Map, Value};
+
+use super::shell_quote;
+use crate::{
+    parser::{BodyEncoder, HttpRequest},
+    request::form_pairs,
+};
+
+impl HttpRequest {
+    /// Formats the request as an HTTPie command line, e.g.
+    /// `http POST api.dev/users Authorization:'Bearer x' name=rq id:=1`.
+    /// JSON objects are mapped to `field=value` and `field:=json` items, any other body is sent with `--raw`
+    pub fn to_httpie(&self) -> String {
+        let mut args = vec!["http".to_string()];
+        match self.body_encoder {
+            BodyEncoder::Form => args.push("--form".to_string()),
+            BodyEncoder::Multipart => args.push("--multipart".to_string()),
+            BodyEncoder::Raw | BodyEncoder::NoEncode => {}
+        }
+        if let Some((user, pass)) = &self.digest_auth {
+            args.push("--auth-type=digest".to_string());
+            args.push(format!(
+                "--auth={}",
+                shell_quote(&format!("{}:{}", user, pass))
+            ));
+        } else if let Some((user, pass)) = &self.basic_auth {
+            args.push(format!(
+                "--auth={}",
+                shell_quote(&format!("{}:{}", user, pass))
+            ));
+        }
+
+        let items = match self.body_encoder {
+            _ if self.body.trim().is_empty() => Some(Vec::new()),
+            BodyEncoder::Form | BodyEncoder::Multipart => Some(
+                form_pairs(&self.body)
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", shell_quote(name), shell_quote(value)))
+                    .collect(),
+            ),
+            BodyEncoder::Raw | BodyEncoder::NoEncode => match serde_json::from_str(&self.body) {
+                Ok(Value::Object(fields)) => json_items(&fields),
+                _ => None,
+            },
+        };
+        if items.is_none() {
+            args.push(format!("--raw={}", shell_quote(&self.body)));
+        }
+
+        args.push(self.method.to_string());
+        args.push(shell_quote(&self.full_url()).into_owned());
+
+        let mut headers: Vec<_> = self.headers.iter().collect();
+        headers.sort();
+        for (name, value) in headers {
+            args.push(if value.is_empty() {
+                // `Name:` would remove the header
+                format!("{};", shell_quote(name))
+            } else {
+                format!("{}:{}", shell_quote(name), shell_quote(value))
+            });
+        }
+        args.extend(items.unwrap_or_default());
+
+        args.join(" ")
+    }
+}
+
+/// Maps the fields to request items, `None` when a name can't be expressed as an item
+fn json_items(fields: &Map<String, Value>) -> Option<Vec<String>> {
+    if fields.is_empty() {
+        return None;
+    }
+    fields
+        .iter()
+        .map(|(name, value)| {
+            // Separators and the nested JSON syntax would be misread in the name
+            if name.is_empty() || name.contains(['=', ':', '@', ';', '[', ']', '\\']) {
+                return None;
+            }
+            let name = shell_quote(name);
+            Some(match value {
+                // `=@` embeds a file
+                Value::String(s) if !s.starts_with('@') => format!("{}={}", name, shell_quote(s)),
+                _ => format!("{}:={}", name, shell_quote(&value.to_string())),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+
+    fn httpie(input: &str) -> String {
+        parse(input).unwrap().requests[0].to_httpie()
+    }
+
+    #[test]
+    fn test_get_with_headers() {
+        assert_eq!(
+            httpie(
+                "GET https://api.dev/users?page=2 HTTP/1.1
+Authorization: Bearer x
+Accept: application/json
+
+"
+            ),
+            "http GET 'https://api.dev/users?page=2' Accept:application/json Authorization:'Bearer x'"
+        );
+    }
+
+    #[test]
+    fn test_post_json() {
+        assert_eq!(
+            httpie(
+                r#"POST https://api.dev/users HTTP/1.1
+Content-Type: application/json
+
+{ "name": "rq cli", "id": 1, "tags": ["http"], "handle": "@rq" }
+
+"#
+            ),
+            r#"http POST https://api.dev/users Content-Type:application/json name='rq cli' id:=1 tags:='["http"]' handle:='"@rq"'"#
+        );
+    }
+
+    #[test]
+    fn test_raw_body() {
+        assert_eq!(
+            httpie("POST https://api.dev/items HTTP/1.1\n\n[1, 2]\n\n"),
+            "http --raw='[1, 2]' POST https://api.dev/items"
+        );
+        assert_eq!(
+            httpie("POST https://api.dev/items HTTP/1.1\n\n{ \"a:b\": 1 }\n\n"),
+            r#"http --raw='{ "a:b": 1 }' POST https://api.dev/items"#
+        );
+    }
+
+    #[test]
+    fn test_form() {
+        assert_eq!(
+            httpie("# @form\nPOST https://api.dev/login HTTP/1.1\n\nuser=rq&pass=a%20b\n\n"),
+            "http --form POST https://api.dev/login user=rq pass='a b'"
+        );
+    }
+}
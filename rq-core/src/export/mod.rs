@@ -0,0 +1,37 @@
+//! Conversion of requests to the request file syntax, the wire format and the command lines of other HTTP clients
+
+use std::borrow::Cow;
+
+pub mod curl;
+pub mod http;
+pub mod httpie;
+pub mod snippet;
+
+/// Wraps `arg` in single quotes unless it only contains characters that are safe in a POSIX shell
+pub(crate) fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=,@%+".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        return Cow::Borrowed(arg);
+    }
+    Cow::Owned(format!("'{}'", arg.replace('\'', r"'\''")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shell_quote;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(
+            shell_quote("https://api.dev/users"),
+            "https://api.dev/users"
+        );
+        assert_eq!(shell_quote("Bearer x"), "'Bearer x'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(
+            shell_quote("/users?page=1&size=2"),
+            "'/users?page=1&size=2'"
+        );
+        assert_eq!(shell_quote(""), "''");
+    }
+}
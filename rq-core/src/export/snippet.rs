@@ -0,0 +1,158 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use std::fmt::Write;
+
+use crate::{parser::HttpRequest, request::Response};
+
+const REDACTED: &str = "<redacted>";
+
+impl HttpRequest {
+    /// Formats the request as sent on the wire: the request line with the path and query,
+    /// the `Host` header, the other headers sorted by name and the body
+    pub fn to_wire(&self) -> String {
+        let url = self.full_url();
+        let rest = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+        let (host, target) = match rest.find(['/', '?']) {
+            Some(at) => rest.split_at(at),
+            None => (rest, "/"),
+        };
+        // The query needs a path
+        let target = match target.strip_prefix('?') {
+            Some(query) => format!("/?{}", query),
+            None => target.to_string(),
+        };
+        let version = if self.version.is_empty() {
+            "1.1"
+        } else {
+            &self.version
+        };
+
+        let mut out = format!("{} {} HTTP/{}\n", self.method, target, version);
+        if !self.has_header("host") {
+            let _ = writeln!(out, "Host: {}", host);
+        }
+        let mut headers: Vec<_> = self.headers.iter().collect();
+        headers.sort();
+        for (name, value) in headers {
+            let _ = writeln!(out, "{}: {}", name, value);
+        }
+        if let Some((user, pass)) = &self.basic_auth {
+            let credentials = STANDARD.encode(format!("{}:{}", user, pass));
+            let _ = writeln!(out, "Authorization: Basic {}", credentials);
+        }
+        out.push('\n');
+        out.push_str(&self.body);
+        out
+    }
+}
+
+/// Self-contained text of an exchange for bug reports: the request as sent on the wire followed
+/// by the response. Values of the headers and query parameters named in `redact` are hidden,
+/// ignoring case
+pub fn snippet(req: &HttpRequest, response: &Response, redact: &[String]) -> String {
+    let redacted = |name: &str| redact.iter().any(|r| r.eq_ignore_ascii_case(name.trim()));
+    let mut out = String::new();
+    let wire = req.to_wire();
+    let (head, body) = wire.split_once("\n\n").unwrap_or((&wire, ""));
+    for (i, line) in head.lines().enumerate() {
+        match line.split_once(':') {
+            _ if i == 0 => out.push_str(&redact_query(line, redacted)),
+            Some((name, _)) if redacted(name) => {
+                let _ = write!(out, "{}: {}", name, REDACTED);
+            }
+            _ => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    let _ = write!(out, "\n{}\n\n", body.trim_end());
+
+    let _ = writeln!(out, "{:?} {}", response.version, response.status_line());
+    for (name, value) in &response.headers {
+        let value = if redacted(name.as_str()) {
+            REDACTED
+        } else {
+            value.to_str().unwrap_or_default()
+        };
+        let _ = writeln!(out, "{}: {}", name, value);
+    }
+    out.push('\n');
+    if response.binary {
+        let _ = write!(out, "<binary body, {} bytes>", response.bytes.len());
+    } else {
+        out.push_str(response.body.trim_end());
+    }
+    out.push('\n');
+    out
+}
+
+/// Hides the values of the query parameters of the request line named in `redact`
+fn redact_query(request_line: &str, redacted: impl Fn(&str) -> bool) -> String {
+    let Some((start, rest)) = request_line.split_once('?') else {
+        return request_line.to_string();
+    };
+    let (query, version) = rest.rsplit_once(' ').unwrap_or((rest, ""));
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if redacted(name) => format!("{}={}", name, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect();
+    format!("{}?{} {}", start, query.join("&"), version)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{header::HeaderMap, StatusCode, Version};
+
+    use super::snippet;
+    use crate::{parser::parse, request::Response};
+
+    #[test]
+    fn test_to_wire() {
+        let req = &parse("GET https://bob:pw@api.dev?page=2 HTTP/2\nAccept: */*\n\n")
+            .unwrap()
+            .requests[0];
+        assert_eq!(
+            req.to_wire(),
+            "GET /?page=2 HTTP/2\nHost: api.dev\nAccept: */*\nAuthorization: Basic Ym9iOnB3\n\n"
+        );
+    }
+
+    #[test]
+    fn test_snippet() {
+        let req = &parse(
+            "POST https://api.dev/users?api_key=s3cret&page=1 HTTP/1.1\nAuthorization: Bearer token\nContent-Type: application/json\n\n{\"name\": \"rq\"}\n",
+        )
+        .unwrap()
+        .requests[0];
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        headers.insert("set-cookie", "session=abc".parse().unwrap());
+        let response = Response {
+            version: Version::HTTP_11,
+            status: StatusCode::CREATED,
+            headers,
+            body: "{\"id\": 1}".to_string(),
+            ..Default::default()
+        };
+        let redact = ["authorization", "Set-Cookie", "API_KEY"].map(String::from);
+
+        assert_eq!(
+            snippet(req, &response, &redact),
+            "POST /users?api_key=<redacted>&page=1 HTTP/1.1
+Host: api.dev
+Authorization: <redacted>
+Content-Type: application/json
+
+{\"name\": \"rq\"}
+
+HTTP/1.1 201 Created
+content-type: application/json
+set-cookie: <redacted>
+
+{\"id\": 1}
+"
+        );
+    }
+}
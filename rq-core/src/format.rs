@@ -0,0 +1,59 @@
+use serde::Serialize;
+use serde_json::{ser::PrettyFormatter, Serializer, Value};
+
+pub const DEFAULT_INDENT: usize = 2;
+
+/// Pretty prints `input` JSON using `indent` spaces
+pub fn indent_json(input: &str, indent: usize) -> Result<String, serde_json::Error> {
+    let value: Value = serde_json::from_str(input)?;
+    let indent = " ".repeat(indent);
+    let mut out = Vec::new();
+    let mut serializer =
+        Serializer::with_formatter(&mut out, PrettyFormatter::with_indent(indent.as_bytes()));
+    value.serialize(&mut serializer)?;
+    // serde_json only writes valid UTF-8
+    Ok(String::from_utf8(out).unwrap())
+}
+
+/// Removes all the insignificant whitespace from `input` JSON
+pub fn minify_json(input: &str) -> Result<String, serde_json::Error> {
+    let value: Value = serde_json::from_str(input)?;
+    serde_json::to_string(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{indent_json, minify_json, DEFAULT_INDENT};
+
+    const INPUT: &str = r#"{"b":[1,2,{"c":null}],"a":"x y"}"#;
+
+    #[test]
+    fn test_indent_json() {
+        assert_eq!(
+            indent_json(INPUT, DEFAULT_INDENT).unwrap(),
+            "{\n  \"b\": [\n    1,\n    2,\n    {\n      \"c\": null\n    }\n  ],\n  \"a\": \"x y\"\n}"
+        );
+        assert_eq!(indent_json(r#"{"a":1}"#, 4).unwrap(), "{\n    \"a\": 1\n}");
+    }
+
+    #[test]
+    fn test_minify_json() {
+        let pretty = indent_json(INPUT, 4).unwrap();
+        assert_eq!(minify_json(&pretty).unwrap(), INPUT);
+    }
+
+    #[test]
+    fn test_idempotency() {
+        let pretty = indent_json(INPUT, DEFAULT_INDENT).unwrap();
+        assert_eq!(indent_json(&pretty, DEFAULT_INDENT).unwrap(), pretty);
+
+        let minified = minify_json(INPUT).unwrap();
+        assert_eq!(minify_json(&minified).unwrap(), minified);
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        assert!(indent_json("{\"a\":", DEFAULT_INDENT).is_err());
+        assert!(minify_json("not json").is_err());
+    }
+}
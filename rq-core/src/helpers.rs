@@ -0,0 +1,86 @@
+//! Helpers evaluated in request bodies: `{{$base64 <text>}}` and `{{$base64File <path>}}`
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use std::{fs, path::Path};
+
+use crate::parser::HttpRequest;
+
+/// Replaces the `{{$helper argument}}` references of `input` with their value.
+/// Files are relative to `base_dir`. Unknown helpers and variables are left as is.
+pub fn evaluate(input: &str, base_dir: &Path) -> Result<String, String> {
+    let mut ret = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let reference = rest[start + 2..start + end].trim();
+        ret.push_str(&rest[..start]);
+        match call(reference, base_dir)? {
+            Some(value) => ret.push_str(&value),
+            None => ret.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    ret.push_str(rest);
+    Ok(ret)
+}
+
+/// Value of a `$`-prefixed reference, `None` if it isn't a known helper
+pub(crate) fn call(reference: &str, base_dir: &Path) -> Result<Option<String>, String> {
+    let (name, argument) = reference
+        .split_once(char::is_whitespace)
+        .unwrap_or((reference, ""));
+    let argument = argument.trim();
+    match name {
+        "$base64" => Ok(Some(STANDARD.encode(argument))),
+        "$base64File" => {
+            let path = base_dir.join(argument);
+            let bytes = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            Ok(Some(STANDARD.encode(bytes)))
+        }
+        _ => Ok(None),
+    }
+}
+
+impl HttpRequest {
+    /// Returns a copy of the request with the helpers of the body evaluated, see [`evaluate`]
+    pub fn with_helpers(&self, base_dir: &Path) -> Result<HttpRequest, String> {
+        let mut ret = self.clone();
+        ret.body = evaluate(&self.body, base_dir)?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::evaluate;
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(
+            evaluate(r#"{"data": "{{$base64 hello rq}}"}"#, Path::new(".")).unwrap(),
+            r#"{"data": "aGVsbG8gcnE="}"#
+        );
+        assert_eq!(
+            evaluate("{{ $base64 }} {{id}} {{$unknown x}}", Path::new(".")).unwrap(),
+            " {{id}} {{$unknown x}}"
+        );
+    }
+
+    #[test]
+    fn test_base64_file() {
+        let dir = env::temp_dir().join(format!("rq-helpers-{}", std::process::id()));
+        fs::create_dir_all(dir.join("keys")).unwrap();
+        fs::write(dir.join("keys/key.pem"), b"\x00key\xff").unwrap();
+
+        let body = evaluate("key={{$base64File keys/key.pem}}", &dir);
+        let missing = evaluate("{{$base64File missing.pem}}", &dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(body.unwrap(), "key=AGtlef8=");
+        assert!(missing.unwrap_err().contains("missing.pem"));
+    }
+}
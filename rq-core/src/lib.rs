@@ -1,5 +1,27 @@
 #[macro_use]
 extern crate pest_derive;
 
+pub mod assertions;
+pub mod auth;
+pub mod body_digest;
+pub mod builder;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod compression;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cookies;
+pub mod diff;
+pub mod encoding;
+pub mod environment;
+pub mod exchange;
+pub mod export;
+pub mod format;
+pub mod helpers;
+pub mod mime;
+pub mod negotiation;
 pub mod parser;
+pub mod progress;
 pub mod request;
+pub mod runtime;
+pub mod sse;
+pub mod variables;
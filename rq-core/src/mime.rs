@@ -0,0 +1,164 @@
+//! Parsing of `multipart/*` bodies, e.g. `multipart/mixed` and `multipart/related` responses
+
+/// Part of a multipart body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Part {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parts of a nested multipart body
+    pub fn parts(&self) -> Option<Vec<Part>> {
+        parse_multipart(&self.body, self.header("content-type")?)
+    }
+}
+
+/// Boundary of a `multipart/*` content type, quoted or not
+pub fn boundary(content_type: &str) -> Option<&str> {
+    let mut params = content_type.split(';');
+    let media_type = params.next()?.trim();
+    if !media_type
+        .get(..10)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("multipart/"))
+    {
+        return None;
+    }
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"'))
+        .filter(|boundary| !boundary.is_empty())
+}
+
+/// Splits a multipart body into its parts. Returns `None` if `content_type` is not multipart
+pub fn parse_multipart(body: &[u8], content_type: &str) -> Option<Vec<Part>> {
+    let delimiter = format!("--{}", boundary(content_type)?).into_bytes();
+    let mut parts = Vec::new();
+    // The preamble before the first delimiter is ignored
+    let mut rest = match find_delimiter(body, &delimiter, 0) {
+        Some(at) => &body[at + delimiter.len()..],
+        None => return Some(parts),
+    };
+    loop {
+        // `--` after the delimiter closes the body, the epilogue is ignored
+        if rest.starts_with(b"--") {
+            break;
+        }
+        // Transport padding and the line break after the delimiter
+        let content_start = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(rest.len(), |i| i + 1);
+        let content = &rest[content_start..];
+        let (end, next) = match find_delimiter(content, &delimiter, 1) {
+            Some(at) => (trim_line_break(&content[..at]).len(), at + delimiter.len()),
+            // Unterminated body, the last part runs until the end
+            None => (content.len(), content.len()),
+        };
+        parts.push(parse_part(&content[..end]));
+        if next >= content.len() {
+            break;
+        }
+        rest = &content[next..];
+    }
+    Some(parts)
+}
+
+/// Offset of the first delimiter at the start of a line, from `from` onwards
+fn find_delimiter(body: &[u8], delimiter: &[u8], from: usize) -> Option<usize> {
+    (from..body.len())
+        .find(|&i| body[i..].starts_with(delimiter) && (i == 0 || body[i - 1] == b'\n'))
+}
+
+/// Removes the line break that belongs to the next delimiter
+fn trim_line_break(content: &[u8]) -> &[u8] {
+    let content = content.strip_suffix(b"\n").unwrap_or(content);
+    content.strip_suffix(b"\r").unwrap_or(content)
+}
+
+fn parse_part(content: &[u8]) -> Part {
+    let (head, body) = match content
+        .strip_prefix(b"\r\n")
+        .or_else(|| content.strip_prefix(b"\n"))
+    {
+        // A part without headers starts with the empty line
+        Some(body) => (&content[..0], body),
+        // The first empty line ends the headers, with either line break
+        None => [&b"\r\n\r\n"[..], b"\n\n"]
+            .iter()
+            .filter_map(|separator| {
+                let at = content
+                    .windows(separator.len())
+                    .position(|w| w == *separator)?;
+                Some((at, at + separator.len()))
+            })
+            .min()
+            .map_or((content, &content[..0]), |(end, start)| {
+                (&content[..end], &content[start..])
+            }),
+    };
+    let headers = String::from_utf8_lossy(head)
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Part {
+        headers,
+        body: body.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{boundary, parse_multipart};
+
+    #[test]
+    fn test_boundary() {
+        assert_eq!(boundary("multipart/mixed; boundary=abc"), Some("abc"));
+        assert_eq!(
+            boundary(r#"Multipart/Related; type="application/json"; boundary="a b:c""#),
+            Some("a b:c")
+        );
+        assert_eq!(boundary("text/plain; boundary=abc"), None);
+        assert_eq!(boundary("multipart/mixed"), None);
+    }
+
+    #[test]
+    fn test_parse_multipart() {
+        let body = b"preamble\r\n--sep\r\nContent-Type: application/json\r\nContent-Id: <1>\r\n\r\n{\"id\": 1}\r\n--sep\r\n\r\nno headers\n--sep\r\nContent-Type: multipart/alternative; boundary=\"inner sep\"\r\n\r\n--inner sep\r\nContent-Type: text/plain\r\n\r\nhello\r\n--inner sep\r\nContent-Type: text/html\r\n\r\n<p>hello</p>\r\n--inner sep--\r\n--sep--\r\nepilogue";
+        let parts = parse_multipart(body, "multipart/mixed; boundary=sep").unwrap();
+        assert_eq!(parts.len(), 3);
+
+        assert_eq!(
+            parts[0].headers,
+            [
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Content-Id".to_string(), "<1>".to_string()),
+            ]
+        );
+        assert_eq!(parts[0].body, b"{\"id\": 1}");
+        assert!(parts[1].headers.is_empty());
+        assert_eq!(parts[1].body, b"no headers");
+        assert!(parts[0].parts().is_none());
+
+        let nested = parts[2].parts().unwrap();
+        assert_eq!(nested.len(), 2);
+        assert_eq!(nested[0].header("content-type"), Some("text/plain"));
+        assert_eq!(nested[0].body, b"hello");
+        assert_eq!(nested[1].body, b"<p>hello</p>");
+
+        assert!(parse_multipart(body, "application/json").is_none());
+        assert_eq!(
+            parse_multipart(b"no delimiter", "multipart/mixed; boundary=sep"),
+            Some(Vec::new())
+        );
+    }
+}
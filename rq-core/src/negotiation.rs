@@ -0,0 +1,65 @@
+use crate::parser::HttpRequest;
+
+/// Media type for the short names of `# @accept-matrix`. Other values are used as written
+pub fn media_type(name: &str) -> String {
+    match name.to_ascii_lowercase().as_str() {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "html" => "text/html",
+        "text" | "plain" => "text/plain",
+        "csv" => "text/csv",
+        "yaml" => "application/yaml",
+        "cbor" => "application/cbor",
+        _ => name,
+    }
+    .to_string()
+}
+
+impl HttpRequest {
+    /// One request per media type of the accept matrix, with the `Accept` header replaced.
+    /// Empty when the request has no `# @accept-matrix`
+    pub fn accept_variants(&self) -> Vec<(String, HttpRequest)> {
+        self.accept_matrix
+            .iter()
+            .map(|name| {
+                let media_type = media_type(name);
+                let mut req = self.clone();
+                req.accept_matrix.clear();
+                req.headers.retain(|k, _| !k.eq_ignore_ascii_case("accept"));
+                req.headers.insert("Accept".to_string(), media_type.clone());
+                (media_type, req)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+
+    #[test]
+    fn test_accept_variants() {
+        let input = "# @accept-matrix json, xml,text/csv\nGET https://api.dev/users HTTP/1.1\naccept: */*\nX-Id: 1\n\n";
+        let req = &parse(input).unwrap().requests[0];
+        assert_eq!(req.accept_matrix, ["json", "xml", "text/csv"]);
+
+        let variants = req.accept_variants();
+        let media_types: Vec<&str> = variants.iter().map(|(m, _)| m.as_str()).collect();
+        assert_eq!(
+            media_types,
+            ["application/json", "application/xml", "text/csv"]
+        );
+        for (media_type, variant) in &variants {
+            assert_eq!(variant.headers.len(), 2);
+            assert_eq!(variant.headers["Accept"], *media_type);
+            assert_eq!(variant.headers["X-Id"], "1");
+            assert_eq!(variant.url, req.url);
+            assert!(variant.accept_variants().is_empty());
+        }
+
+        let req = &parse("GET https://api.dev/users HTTP/1.1\n\n")
+            .unwrap()
+            .requests[0];
+        assert!(req.accept_variants().is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+use std::{fmt::Display, time::Duration};
+
+/// Download progress of a response body
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub received: u64,
+    /// `Content-Length` of the response, when sent
+    pub total: Option<u64>,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Bytes per second, `None` before any time elapsed
+    pub fn throughput(&self) -> Option<f64> {
+        let secs = self.elapsed.as_secs_f64();
+        (secs > 0.0).then(|| self.received as f64 / secs)
+    }
+
+    pub fn percent(&self) -> Option<u64> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| (self.received.min(total) * 100) / total)
+    }
+
+    /// Time left at the current throughput
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total?.saturating_sub(self.received);
+        match self.throughput()? {
+            _ if remaining == 0 => Some(Duration::ZERO),
+            throughput if throughput > 0.0 => {
+                Some(Duration::from_secs_f64(remaining as f64 / throughput))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for Progress {
+    /// `45% of 2.0 MiB, ETA 3s`, or `1.2 MiB at 340.0 KiB/s` without a `Content-Length`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.percent(), self.total) {
+            (Some(percent), Some(total)) => {
+                write!(f, "{}% of {}", percent, format_bytes(total))?;
+                if let Some(eta) = self.eta() {
+                    write!(f, ", ETA {}s", eta.as_secs_f64().ceil())?;
+                }
+                Ok(())
+            }
+            _ => {
+                write!(f, "{}", format_bytes(self.received))?;
+                if let Some(throughput) = self.throughput() {
+                    write!(f, " at {}/s", format_bytes(throughput as u64))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Formats a size with binary units, e.g. `1.5 KiB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format_bytes, Progress};
+
+    #[test]
+    fn test_known_length() {
+        let mut progress = Progress {
+            received: 0,
+            total: Some(4 * 1024 * 1024),
+            elapsed: Duration::ZERO,
+        };
+        assert_eq!(progress.percent(), Some(0));
+        assert_eq!(progress.eta(), None);
+        assert_eq!(progress.to_string(), "0% of 4.0 MiB");
+
+        // 1 MiB per second
+        progress.received = 1024 * 1024;
+        progress.elapsed = Duration::from_secs(1);
+        assert_eq!(progress.percent(), Some(25));
+        assert_eq!(progress.throughput(), Some(1024.0 * 1024.0));
+        assert_eq!(progress.eta(), Some(Duration::from_secs(3)));
+        assert_eq!(progress.to_string(), "25% of 4.0 MiB, ETA 3s");
+
+        progress.received = 4 * 1024 * 1024;
+        progress.elapsed = Duration::from_millis(2500);
+        assert_eq!(progress.percent(), Some(100));
+        assert_eq!(progress.eta(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_unknown_length() {
+        let progress = Progress {
+            received: 3 * 512 * 1024,
+            total: None,
+            elapsed: Duration::from_secs(3),
+        };
+        assert_eq!(progress.percent(), None);
+        assert_eq!(progress.eta(), None);
+        assert_eq!(progress.to_string(), "1.5 MiB at 512.0 KiB/s");
+
+        let progress = Progress {
+            received: 100,
+            ..Default::default()
+        };
+        assert_eq!(progress.to_string(), "100 B");
+    }
+
+    #[test]
+    fn test_empty_total() {
+        let progress = Progress {
+            received: 0,
+            total: Some(0),
+            elapsed: Duration::from_secs(1),
+        };
+        assert_eq!(progress.percent(), None);
+        assert_eq!(progress.to_string(), "0 B at 0 B/s");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
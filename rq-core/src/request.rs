@@ -1,40 +1,1148 @@
 extern crate reqwest;
 
-use reqwest::{header, Client, Method};
+mod body;
 
-use crate::parser::HttpRequest;
-use std::{str::FromStr, time::Duration};
+use bytes::Bytes;
+use reqwest::{header, multipart, Client, Method, StatusCode, Version};
 
-fn new_client() -> Client {
+use self::body::BodyKind;
+use crate::{
+    body_digest::digest_header,
+    compression::{decompress, Compression},
+    encoding::decode_with_encoding,
+    parser::{BodyEncoder, HttpMethod, HttpRequest},
+    runtime,
+};
+use std::{fmt::Display, time::Duration};
+
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    crate::auth::{
+        aws::{self, AwsCredentials},
+        oauth2::{self, TokenCache},
+    },
+    reqwest::cookie::Jar,
+    std::{
+        fs,
+        path::PathBuf,
+        sync::Arc,
+        time::{Instant, SystemTime},
+    },
+};
+
+impl From<&HttpMethod> for Method {
+    fn from(method: &HttpMethod) -> Self {
+        match method {
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Post => Method::POST,
+            HttpMethod::Put => Method::PUT,
+            HttpMethod::Delete => Method::DELETE,
+            HttpMethod::Patch => Method::PATCH,
+            HttpMethod::Head => Method::HEAD,
+            HttpMethod::Options => Method::OPTIONS,
+            HttpMethod::Connect => Method::CONNECT,
+        }
+    }
+}
+
+/// Options applied to the client used to send requests
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    /// `User-Agent` for requests that don't define one. Uses reqwest's default when `None`
+    pub default_user_agent: Option<String>,
+    /// `Host` sent instead of the one of the url, unless the request defines one
+    pub host: Option<String>,
+    /// Cookies sent with the requests, updated by their responses. Skipped by `# @no-cookie-store`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub cookie_jar: Option<Arc<Jar>>,
+    /// Tokens of the `# @oauth2` requests, shared by the clones of the config
+    #[cfg(not(target_arch = "wasm32"))]
+    pub oauth2_tokens: Arc<TokenCache>,
+    /// Directory the `< path` bodies are relative to, usually the one of the requests file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub base_dir: PathBuf,
+    /// Overall timeout of the requests without `# @timeout`, 10 seconds when `None`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub timeout: Option<Duration>,
+}
+
+/// Default overall timeout, overridden by `ClientConfig::timeout` and `# @timeout`
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Overall timeout applied to `req`
+#[cfg(not(target_arch = "wasm32"))]
+fn request_timeout(req: &HttpRequest, config: &ClientConfig) -> Duration {
+    req.timeout.or(config.timeout).unwrap_or(DEFAULT_TIMEOUT)
+}
+
+/// Replaces reqwest's timeout error, which only tells the url, with the timeout that expired
+#[cfg(not(target_arch = "wasm32"))]
+fn timed_out(
+    err: Box<dyn std::error::Error + Send + Sync>,
+    req: &HttpRequest,
+    config: &ClientConfig,
+) -> Box<dyn std::error::Error + Send + Sync> {
+    match err.downcast_ref::<reqwest::Error>() {
+        Some(e) if e.is_timeout() => match req.connect_timeout_ms {
+            // A connect timeout is also reported as a connect error
+            Some(ms) if e.is_connect() => format!("connect timed out after {}ms", ms).into(),
+            _ => {
+                let timeout = request_timeout(req, config);
+                let timeout = match timeout.subsec_millis() {
+                    0 => format!("{}s", timeout.as_secs()),
+                    _ => format!("{}ms", timeout.as_millis()),
+                };
+                format!("request timed out after {}", timeout).into()
+            }
+        },
+        _ => err,
+    }
+}
+
+fn new_client(
+    config: &ClientConfig,
+    req: &HttpRequest,
+) -> Result<Client, Box<dyn std::error::Error + Send + Sync>> {
+    // No default `Content-Type`, requests only send the one they declare
     let mut headers = header::HeaderMap::new();
-    headers.insert(
-        header::CONTENT_TYPE,
-        header::HeaderValue::from_static("application/json"),
-    );
     headers.insert(
         header::ACCEPT,
         header::HeaderValue::from_static("application/json"),
     );
 
-    Client::builder()
-        .timeout(Duration::from_secs(10))
-        .default_headers(headers)
-        .no_gzip()
-        .build()
-        .unwrap()
+    let mut builder = Client::builder().default_headers(headers);
+    // Timeouts and compression are handled by the browser on wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        // `# @timeout` is set on each request so that session clients can be shared
+        builder = builder
+            .timeout(config.timeout.unwrap_or(DEFAULT_TIMEOUT))
+            .no_gzip();
+        if let Some(ms) = req.connect_timeout_ms {
+            builder = builder.connect_timeout(Duration::from_millis(ms));
+        }
+        if let Some(jar) = config.cookie_jar.as_ref().filter(|_| !req.no_cookie_store) {
+            builder = builder.cookie_provider(jar.clone());
+        }
+    }
+    if let Some(user_agent) = &config.default_user_agent {
+        // The builder only reports a generic error for invalid values
+        header::HeaderValue::from_str(user_agent)
+            .map_err(|_| format!("invalid default user agent {:?}", user_agent))?;
+        builder = builder.user_agent(user_agent);
+    }
+
+    Ok(builder.build()?)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Response {
+    pub version: Version,
+    pub status: StatusCode,
+    /// Reason phrase of the status line when it differs from the canonical one
+    pub reason: Option<String>,
+    pub headers: header::HeaderMap,
+    /// Body decoded with the response charset. Lossy for binary bodies
+    pub body: String,
+    pub bytes: Bytes,
+    /// Whether the body couldn't be decoded as text
+    pub binary: bool,
+    /// Set when the body was compressed by the server. `bytes` holds the decompressed body
+    pub compression: Option<Compression>,
+    /// Whether `body` is the JSON representation of a CBOR body. `bytes` holds the CBOR body
+    pub cbor: bool,
+    /// Time from sending the request until the whole body is received. Zero when unknown
+    pub elapsed: Duration,
+}
+
+impl Response {
+    /// Status code and reason phrase, e.g. `418 I'm a teapot`
+    pub fn status_line(&self) -> String {
+        match self.reason.as_deref().or(self.status.canonical_reason()) {
+            Some(reason) => format!("{} {}", self.status.as_str(), reason),
+            None => self.status.as_str().to_string(),
+        }
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+    }
+}
+
+impl Response {
+    /// Status line and headers, as printed before the body
+    pub fn head(&self) -> String {
+        let mut head = format!("{:?} {}", self.version, self.status_line());
+        if !self.elapsed.is_zero() {
+            head.push_str(&format!(" — {}", format_elapsed(self.elapsed)));
+        }
+        head.push('\n');
+        for (k, v) in self.headers.iter() {
+            head.push_str(&format!("{}: {}\n", k, v.to_str().unwrap_or_default()));
+        }
+        head
+    }
+}
+
+impl Display for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.head())?;
+        if self.binary {
+            write!(f, "\n<binary body, {} bytes>", self.bytes.len())
+        } else {
+            write!(f, "\n{}", self.body)
+        }
+    }
+}
+
+/// Milliseconds, or microseconds below a millisecond, e.g. `143ms` or `820µs`
+pub fn format_elapsed(elapsed: Duration) -> String {
+    if elapsed < Duration::from_millis(1) {
+        format!("{}µs", elapsed.as_micros())
+    } else {
+        format!("{}ms", elapsed.as_millis())
+    }
 }
 
 pub async fn execute(
     req: &HttpRequest,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let request =
-        new_client().request(Method::from_str(req.method.to_string().as_str())?, &req.url);
+    config: &ClientConfig,
+) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+    execute_with_progress(req, config, |_, _| {}).await
+}
+
+/// Like [`execute`], calling `on_progress` with the bytes of the body received so far
+/// and the `Content-Length` of the response, if any
+pub async fn execute_with_progress(
+    req: &HttpRequest,
+    config: &ClientConfig,
+    on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+    send(&new_client(config, req)?, req, config, on_progress).await
+}
+
+/// Client kept across the requests of a session, so that they share the cookies of their
+/// responses, e.g. a login and the requests that follow it. Requests with
+/// `# @no-cookie-store` or `# @timeout-connect` get a client of their own
+#[derive(Debug, Clone)]
+pub struct Session {
+    client: Client,
+    config: ClientConfig,
+}
+
+impl Session {
+    /// Builds the session client, with a new cookie jar unless `config` has one
+    pub fn new(config: ClientConfig) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let config = ClientConfig {
+            cookie_jar: Some(config.cookie_jar.clone().unwrap_or_default()),
+            ..config
+        };
+        let client = new_client(&config, &HttpRequest::default())?;
+        Ok(Self { client, config })
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    pub async fn execute(
+        &self,
+        req: &HttpRequest,
+    ) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+        self.execute_with_progress(req, |_, _| {}).await
+    }
+
+    /// Like [`Session::execute`], see [`execute_with_progress`]
+    pub async fn execute_with_progress(
+        &self,
+        req: &HttpRequest,
+        on_progress: impl FnMut(u64, Option<u64>),
+    ) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+        // Both are options of the client
+        if req.no_cookie_store || req.connect_timeout_ms.is_some() {
+            let client = new_client(&self.config, req)?;
+            return send(&client, req, &self.config, on_progress).await;
+        }
+        send(&self.client, req, &self.config, on_progress).await
+    }
+}
+
+impl Default for Session {
+    /// Session with the default config. Panics if the client can't be built, like
+    /// `reqwest::Client::new`
+    fn default() -> Self {
+        Self::new(ClientConfig::default()).expect("default client")
+    }
+}
+
+async fn send(
+    client: &Client,
+    req: &HttpRequest,
+    config: &ClientConfig,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<Response, Box<dyn std::error::Error + Send + Sync>> {
+    let request = client.request(Method::from(&req.method), req.full_url());
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let request = match http_version(&req.version) {
+        Some(version) => request.version(version),
+        None => request,
+    };
+
+    let mut headers: header::HeaderMap = (&req.headers).try_into()?;
+
+    if let Some(host) = &config.host {
+        if !headers.contains_key(header::HOST) {
+            headers.insert(header::HOST, host.parse()?);
+        }
+    }
+
+    // An explicit `Authorization` header takes precedence over the token
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(oauth) = &req.oauth2 {
+        if !headers.contains_key(header::AUTHORIZATION) {
+            let token = oauth2::token(oauth, config).await?;
+            headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse()?);
+        }
+    }
+
+    // Read when sent so that a missing file fails the request, not the parsing of the file
+    #[cfg(not(target_arch = "wasm32"))]
+    let file_body = match &req.body_file {
+        Some(path) => {
+            let path = config.base_dir.join(path);
+            Some(fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?)
+        }
+        None => None,
+    };
+    #[cfg(target_arch = "wasm32")]
+    let file_body: Option<Vec<u8>> = match &req.body_file {
+        Some(_) => return Err("file bodies are not supported in the browser".into()),
+        None => None,
+    };
+
+    // Variables are already resolved so the digest covers the body that is sent
+    if let Some(algorithm) = req.body_digest {
+        if !req.has_header("digest") {
+            if let Some(body) = file_body.clone().or_else(|| encoded_body(req)) {
+                headers.insert("digest", digest_header(algorithm, &body).parse()?);
+            }
+        }
+    }
+
+    // Only `@form` and `@multipart` set a content type, which an explicit header
+    // or `@no-content-type` replaces when the headers are applied below
+    if req.empty_content_type && !headers.contains_key(header::CONTENT_TYPE) {
+        headers.insert(header::CONTENT_TYPE, header::HeaderValue::from_static(""));
+    }
+
+    // Signed last so that the signature covers the other headers. An explicit
+    // `Authorization` header takes precedence
+    #[cfg(not(target_arch = "wasm32"))]
+    if req.aws_sigv4.is_some() && !headers.contains_key(header::AUTHORIZATION) {
+        let credentials = aws::credentials(req.aws_profile.as_deref())?;
+        let body = file_body.clone().or_else(|| encoded_body(req));
+        sign_aws(
+            &mut headers,
+            req,
+            body.as_deref(),
+            &credentials,
+            SystemTime::now(),
+        )?;
+    }
+    #[cfg(target_arch = "wasm32")]
+    if req.aws_sigv4.is_some() {
+        return Err("AWS signing is not supported in the browser".into());
+    }
+
+    let request = if let Some(body) = file_body {
+        request.body(body)
+    } else {
+        match req.body_encoder {
+            BodyEncoder::Raw => match body::classify(&req.headers, &req.body) {
+                BodyKind::Form(pairs) => request.form(&pairs),
+                BodyKind::Raw | BodyKind::Json => request.body(req.body.clone()),
+            },
+            BodyEncoder::Form => request.form(&form_pairs(&req.body)),
+            BodyEncoder::Multipart => {
+                let form = form_pairs(&req.body)
+                    .into_iter()
+                    .fold(multipart::Form::new(), |form, (k, v)| form.text(k, v));
+                request.multipart(form)
+            }
+            BodyEncoder::NoEncode => request.body(Bytes::from(req.body.as_bytes().to_vec())),
+        }
+    };
+
+    let request = match &req.basic_auth {
+        Some((user, pass)) => request.basic_auth(user, Some(pass)),
+        None => request,
+    };
+
+    let request = request.headers(headers);
+    #[cfg(not(target_arch = "wasm32"))]
+    let request = request.timeout(request_timeout(req, config));
+    // Timers are not available on wasm
+    #[cfg(not(target_arch = "wasm32"))]
+    let started = Instant::now();
+    let send = async {
+        match &req.digest_auth {
+            #[cfg(not(target_arch = "wasm32"))]
+            Some((user, password)) => crate::auth::digest::send(request, user, password).await,
+            _ => Ok(request.send().await?),
+        }
+    };
+    // `send` completes once the response headers are received
+    let res = match req.read_timeout_ms {
+        Some(ms) => runtime::timeout(Duration::from_millis(ms), send)
+            .await
+            .ok_or_else(|| format!("read timeout: no response after {}ms", ms))?,
+        None => send.await,
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let res = res.map_err(|e| timed_out(e, req, config));
+    let res = res?;
+
+    let version = res.version();
+    let status = res.status();
+    #[cfg(not(target_arch = "wasm32"))]
+    let reason = res
+        .extensions()
+        .get::<hyper::ext::ReasonPhrase>()
+        .map(|reason| String::from_utf8_lossy(reason.as_bytes()).to_string());
+    #[cfg(target_arch = "wasm32")]
+    let reason = None;
+    let mut headers = res.headers().clone();
+    // The wasm client can't read the body in chunks
+    #[cfg(target_arch = "wasm32")]
+    let mut bytes = {
+        let bytes = res.bytes().await?;
+        on_progress(bytes.len() as u64, Some(bytes.len() as u64));
+        bytes
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut bytes = {
+        let mut res = res;
+        let total = res.content_length();
+        let mut body = Vec::new();
+        while let Some(chunk) = res
+            .chunk()
+            .await
+            .map_err(|e| timed_out(e.into(), req, config))?
+        {
+            body.extend_from_slice(&chunk);
+            on_progress(body.len() as u64, total);
+        }
+        Bytes::from(body)
+    };
+    // Responses are not decompressed by the client so a request `Accept-Encoding` is sent as is.
+    // Bodies that fail to decompress are kept as received
+    let mut compression = None;
+    if let Some(encoding) = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+    {
+        if let Some(decompressed) = decompress(encoding, &bytes) {
+            compression = Some(Compression {
+                encoding: encoding.to_string(),
+                compressed: bytes.len(),
+                decompressed: decompressed.len(),
+            });
+            bytes = Bytes::from(decompressed);
+        }
+    }
+    // The headers describe the decompressed body, the encoding is kept in `compression`
+    if compression.is_some() {
+        headers.remove(header::CONTENT_ENCODING);
+        if headers.contains_key(header::CONTENT_LENGTH) {
+            headers.insert(header::CONTENT_LENGTH, bytes.len().into());
+        }
+    }
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    #[cfg(feature = "cbor")]
+    let cbor = content_type
+        .is_some_and(|ct| ct.starts_with("application/cbor"))
+        .then(|| crate::cbor::cbor_to_json(&bytes))
+        .flatten();
+    #[cfg(not(feature = "cbor"))]
+    let cbor: Option<String> = None;
+
+    let (body, binary) = match cbor.as_ref() {
+        Some(json) => (json.clone(), false),
+        None => match decode_with_encoding(&bytes, content_type) {
+            Some(body) => (body, false),
+            None => (String::from_utf8_lossy(&bytes).to_string(), true),
+        },
+    };
+
+    Ok(Response {
+        version,
+        status,
+        reason,
+        headers,
+        body,
+        bytes,
+        binary,
+        compression,
+        cbor: cbor.is_some(),
+        #[cfg(not(target_arch = "wasm32"))]
+        elapsed: started.elapsed(),
+        #[cfg(target_arch = "wasm32")]
+        elapsed: Duration::ZERO,
+    })
+}
+
+/// Adds the headers of `# @auth aws-sigv4` to `headers`, signed with `credentials`
+#[cfg(not(target_arch = "wasm32"))]
+fn sign_aws(
+    headers: &mut header::HeaderMap,
+    req: &HttpRequest,
+    body: Option<&[u8]>,
+    credentials: &AwsCredentials,
+    now: SystemTime,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if let Some((region, service)) = &req.aws_sigv4 {
+        aws::sign(
+            headers,
+            &req.method.to_string(),
+            &reqwest::Url::parse(&req.full_url())?,
+            body,
+            credentials,
+            region,
+            service,
+            now,
+        )?;
+    }
+    Ok(())
+}
+
+/// Body as it is sent. `None` for multipart bodies, whose boundary is only known when sent
+fn encoded_body(req: &HttpRequest) -> Option<Vec<u8>> {
+    let encode = |pairs: Vec<(String, String)>| {
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish()
+            .into_bytes()
+    };
+    match req.body_encoder {
+        // Raw bodies declared as `application/x-www-form-urlencoded` are sent as forms
+        BodyEncoder::Raw => match body::classify(&req.headers, &req.body) {
+            BodyKind::Form(pairs) => Some(encode(pairs)),
+            BodyKind::Raw | BodyKind::Json => Some(req.body.as_bytes().to_vec()),
+        },
+        BodyEncoder::NoEncode => Some(req.body.as_bytes().to_vec()),
+        BodyEncoder::Form => Some(encode(form_pairs(&req.body))),
+        BodyEncoder::Multipart => None,
+    }
+}
+
+/// Version to send the request with. HTTP/2 is left to the negotiation of the
+/// client since forcing it fails with HTTP/1 servers
+#[cfg(not(target_arch = "wasm32"))]
+fn http_version(version: &str) -> Option<Version> {
+    match version {
+        "1.0" => Some(Version::HTTP_10),
+        "1.1" => Some(Version::HTTP_11),
+        _ => None,
+    }
+}
+
+pub(crate) fn form_pairs(body: &str) -> Vec<(String, String)> {
+    // Form bodies can be split in multiple lines for readability
+    let body: String = body.lines().map(str::trim).collect();
+    form_urlencoded::parse(body.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        thread,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use reqwest::{
+        header::{self, HeaderMap},
+        Method, StatusCode,
+    };
+
+    use super::{
+        execute, execute_with_progress, format_elapsed, sign_aws, ClientConfig, Response, Session,
+    };
+    use crate::{
+        auth::aws::AwsCredentials,
+        parser::{parse, HttpMethod},
+    };
+
+    #[tokio::test]
+    async fn test_read_timeout() {
+        // Accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (_stream, _) = listener.accept().unwrap();
+            thread::sleep(std::time::Duration::from_secs(2));
+        });
+        let file = parse(&format!(
+            "# @timeout-connect 1000\n# @timeout-read 100\nGET {}/ HTTP/1.1\n\n",
+            url
+        ))
+        .unwrap();
+
+        let err = execute(&file.requests[0], &ClientConfig::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "read timeout: no response after 100ms");
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        // Answers each connection after 300ms
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                thread::spawn(move || {
+                    let mut buf = [0; 1024];
+                    let _ = stream.read(&mut buf).unwrap();
+                    thread::sleep(std::time::Duration::from_millis(300));
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+                });
+            }
+        });
+        let file = parse(&format!(
+            "# @timeout 0.1\nGET {}/ HTTP/1.1\n\n###\n\nGET {}/ HTTP/1.1\n\n",
+            url, url
+        ))
+        .unwrap();
+
+        let err = execute(&file.requests[0], &ClientConfig::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "request timed out after 100ms");
+        // The default timeout applies to the requests without the annotation
+        let response = execute(&file.requests[1], &ClientConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(response.body, "ok");
+
+        // The configured timeout replaces the default one, not the annotation
+        let config = ClientConfig {
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        let err = execute(&file.requests[1], &config).await.unwrap_err();
+        assert_eq!(err.to_string(), "request timed out after 200ms");
+        let config = ClientConfig {
+            timeout: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let err = execute(&file.requests[0], &config).await.unwrap_err();
+        assert_eq!(err.to_string(), "request timed out after 100ms");
+
+        // Sessions share a client built with the configured timeout only
+        let session = Session::new(config).unwrap();
+        let err = session.execute(&file.requests[0]).await.unwrap_err();
+        assert_eq!(err.to_string(), "request timed out after 100ms");
+        let response = session.execute(&file.requests[1]).await.unwrap();
+        assert_eq!(response.body, "ok");
+    }
+
+    /// Serves a single response with `headers` and `body`, and sends back the raw request
+    pub(crate) fn serve(headers: &str, body: Vec<u8>) -> (String, mpsc::Receiver<String>) {
+        serve_status("200 OK", headers, body)
+    }
+
+    /// Like [`serve`], answering with `status`, e.g. `404 Not Found`
+    fn serve_status(
+        status: &str,
+        headers: &str,
+        body: Vec<u8>,
+    ) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let status = status.to_string();
+        let headers = headers.to_string();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            // The body may come in the same chunk as the headers
+            let head_len = request.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+            let content_length = String::from_utf8_lossy(&request[..head_len])
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                .unwrap_or_default();
+            while request.len() < head_len + content_length {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\n{}Content-Length: {}\r\n\r\n",
+                status,
+                headers,
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+            tx.send(String::from_utf8(request).unwrap()).unwrap();
+        });
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_form_content_type() {
+        let body = "q=a%26b%3Dc&name=rq's cli";
+        let sent_body = |content_type: &str| {
+            let (url, rx) = serve("", Vec::new());
+            let input = format!(
+                "POST {}/ HTTP/1.1\nContent-Type: {}\n\n{}\n",
+                url, content_type, body
+            );
+            async move {
+                let file = parse(&input).unwrap();
+                execute(&file.requests[0], &ClientConfig::default())
+                    .await
+                    .unwrap();
+                let request = rx.recv().unwrap();
+                request.split_once("\r\n\r\n").unwrap().1.to_string()
+            }
+        };
+
+        // `&` and `=` in the values stay encoded, the other characters are encoded
+        assert_eq!(
+            sent_body("application/x-www-form-urlencoded; charset=utf-8").await,
+            "q=a%26b%3Dc&name=rq%27s+cli"
+        );
+        assert_eq!(sent_body("application/json").await, body);
+    }
+
+    #[tokio::test]
+    async fn test_session_cookies() {
+        // The session has a cookie jar of its own
+        let session = Session::default();
+        let (login, _login_rx) = serve("Set-Cookie: session=abc; Path=/\r\n", Vec::new());
+        let (me, me_rx) = serve("", Vec::new());
+        let (other, other_rx) = serve("", Vec::new());
+        let file = parse(&format!(
+            "POST {}/login HTTP/1.1\n\n###\n\nGET {}/me HTTP/1.1\n\n###\n\n# @no-cookie-store\nGET {}/me HTTP/1.1\n\n",
+            login, me, other
+        ))
+        .unwrap();
+        for req in &file.requests {
+            session.execute(req).await.unwrap();
+        }
+
+        // Cookies don't depend on the port
+        let request = me_rx.recv().unwrap().to_lowercase();
+        assert!(request.contains("cookie: session=abc\r\n"), "{}", request);
+        let request = other_rx.recv().unwrap().to_lowercase();
+        assert!(!request.contains("cookie"), "{}", request);
+    }
+
+    #[test]
+    fn test_method_conversion() {
+        for method in HttpMethod::iterator() {
+            assert_eq!(Method::from(method).as_str(), method.to_string());
+        }
+    }
+
+    #[test]
+    fn test_status_line() {
+        let status_line = |code: u16, reason: Option<&str>| {
+            Response {
+                status: StatusCode::from_u16(code).unwrap(),
+                reason: reason.map(str::to_string),
+                ..Default::default()
+            }
+            .status_line()
+        };
+        assert_eq!(status_line(200, None), "200 OK");
+        assert_eq!(status_line(418, None), "418 I'm a teapot");
+        assert_eq!(status_line(599, None), "599");
+        assert_eq!(
+            status_line(599, Some("Network Timeout")),
+            "599 Network Timeout"
+        );
+    }
+
+    #[test]
+    fn test_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_micros(820)), "820µs");
+        assert_eq!(format_elapsed(Duration::from_micros(143_900)), "143ms");
+
+        let mut response = Response::default();
+        assert!(response.to_string().starts_with("HTTP/1.1 200 OK\n"));
+        response.elapsed = Duration::from_millis(143);
+        assert!(response
+            .to_string()
+            .starts_with("HTTP/1.1 200 OK — 143ms\n"));
+    }
+
+    #[tokio::test]
+    async fn test_non_canonical_reason() {
+        let (url, _rx) = serve_status("599 Network Timeout", "", Vec::new());
+        let file = parse(&format!("GET {}/ HTTP/1.1\n\n", url)).unwrap();
+        let response = execute(&file.requests[0], &ClientConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(response.reason.as_deref(), Some("Network Timeout"));
+        assert!(response
+            .to_string()
+            .starts_with("HTTP/1.1 599 Network Timeout — "));
+
+        let (url, _rx) = serve_status("418 I'm a teapot", "", Vec::new());
+        let file = parse(&format!("GET {}/ HTTP/1.1\n\n", url)).unwrap();
+        let response = execute(&file.requests[0], &ClientConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(response.reason, None);
+        assert_eq!(response.status_line(), "418 I'm a teapot");
+    }
+
+    #[tokio::test]
+    async fn test_host_override() {
+        let config = ClientConfig {
+            host: Some("api.internal".to_string()),
+            ..Default::default()
+        };
+
+        let (url, rx) = serve("", Vec::new());
+        let file = parse(&format!("GET {}/ HTTP/1.1\n\n", url)).unwrap();
+        execute(&file.requests[0], &config).await.unwrap();
+        let request = rx.recv().unwrap().to_lowercase();
+        assert!(request.contains("host: api.internal\r\n"), "{}", request);
+
+        let (url, rx) = serve("", Vec::new());
+        let file = parse(&format!("GET {}/ HTTP/1.1\nHost: request.dev\n\n", url)).unwrap();
+        execute(&file.requests[0], &config).await.unwrap();
+        let request = rx.recv().unwrap().to_lowercase();
+        assert!(request.contains("host: request.dev\r\n"), "{}", request);
+        assert!(!request.contains("api.internal"), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn test_head_request_has_no_body() {
+        let (url, rx) = serve("", b"ignored".to_vec());
+        let file = parse(&format!("HEAD {}/health HTTP/1.1\n\n", url)).unwrap();
+        let response = execute(&file.requests[0], &ClientConfig::default())
+            .await
+            .unwrap();
+        let request = rx.recv().unwrap();
+        assert!(
+            request.starts_with("HEAD /health HTTP/1.1\r\n"),
+            "{}",
+            request
+        );
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.headers["content-length"], "7");
+        assert!(response.body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_default_version() {
+        let (url, rx) = serve("", Vec::new());
+        let file = parse(&format!(
+            "# @default-version HTTP/1.0\n\nGET {}/legacy\n\n",
+            url
+        ))
+        .unwrap();
+        execute(&file.requests[0], &ClientConfig::default())
+            .await
+            .unwrap();
+        let request = rx.recv().unwrap();
+        assert!(
+            request.starts_with("GET /legacy HTTP/1.0\r\n"),
+            "{}",
+            request
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_bearer_token() {
+        let config = ClientConfig::default();
+        let (token_url, token_rx) = serve(
+            "Content-Type: application/json\r\n",
+            br#"{"access_token": "abc123", "token_type": "Bearer", "expires_in": 3600}"#.to_vec(),
+        );
+        let request = |url: &str| {
+            let input = format!(
+                "# @oauth2 token_url={}/token client_id=rq client_secret=secret scope=read\nGET {}/users HTTP/1.1\n\n",
+                token_url, url
+            );
+            parse(&input).unwrap().requests.remove(0)
+        };
+
+        let (url, rx) = serve("", Vec::new());
+        execute(&request(&url), &config).await.unwrap();
+        let token_request = token_rx.recv().unwrap();
+        assert!(
+            token_request.starts_with("POST /token HTTP/1.1\r\n"),
+            "{}",
+            token_request
+        );
+        let request_head = rx.recv().unwrap().to_lowercase();
+        assert!(
+            request_head.contains("authorization: bearer abc123\r\n"),
+            "{}",
+            request_head
+        );
+
+        // The token endpoint only answers once, the cached token is used
+        let (url, rx) = serve("", Vec::new());
+        execute(&request(&url), &config).await.unwrap();
+        assert!(rx.recv().unwrap().contains("Bearer abc123"));
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_token_error() {
+        let (token_url, _rx) = serve_status(
+            "401 Unauthorized",
+            "",
+            br#"{"error": "invalid_client"}"#.to_vec(),
+        );
+        let input = format!(
+            "# @oauth2 token_url={}/token client_id=rq client_secret=wrong\nGET http://127.0.0.1:1/users HTTP/1.1\n\n",
+            token_url
+        );
+        let req = &parse(&input).unwrap().requests[0];
+        let error = execute(req, &ClientConfig::default())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("401 Unauthorized"), "{}", error);
+        assert!(error.contains("invalid_client"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_digest_body() {
+        let (url, rx) = serve("", Vec::new());
+        let file = parse(&format!(
+            "# @digest-body\n# @form\nPOST {}/ HTTP/1.1\n\nname=rq&\nlang=rust",
+            url
+        ))
+        .unwrap();
+        execute(&file.requests[0], &ClientConfig::default())
+            .await
+            .unwrap();
+
+        let request = rx.recv().unwrap();
+        let expected = super::digest_header(Default::default(), b"name=rq&lang=rust");
+        assert!(
+            request.contains(&format!("digest: {}\r\n", expected)),
+            "{}",
+            request
+        );
+    }
+
+    #[tokio::test]
+    async fn test_content_type_is_only_sent_when_declared() {
+        let content_type = |input: &str| {
+            let (url, rx) = serve("", Vec::new());
+            let file = parse(&input.replace("{url}", &url)).unwrap();
+            async move {
+                execute(&file.requests[0], &ClientConfig::default())
+                    .await
+                    .unwrap();
+                let request = rx.recv().unwrap();
+                let head = request.split("\r\n\r\n").next().unwrap().to_string();
+                head.lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+                    .map(|(_, value)| value.trim().to_string())
+            }
+        };
+
+        assert_eq!(
+            content_type("POST {url}/ HTTP/1.1\n\n{\"a\": 1}").await,
+            None
+        );
+        assert_eq!(
+            content_type("# @no-body-encode\nPOST {url}/ HTTP/1.1\n\nbytes").await,
+            None
+        );
+        assert_eq!(
+            content_type("POST {url}/ HTTP/1.1\nContent-Type: text/csv\n\na,b").await,
+            Some("text/csv".to_string())
+        );
+        assert_eq!(
+            content_type("# @form\nPOST {url}/ HTTP/1.1\n\na=1").await,
+            Some("application/x-www-form-urlencoded".to_string())
+        );
+        assert_eq!(
+            content_type("# @no-content-type\nPOST {url}/ HTTP/1.1\n\nbytes").await,
+            Some(String::new())
+        );
+        assert_eq!(
+            content_type("# @no-content-type\n# @form\nPOST {url}/ HTTP/1.1\n\na=1").await,
+            Some(String::new())
+        );
+        assert_eq!(
+            content_type("# @no-content-type\nPOST {url}/ HTTP/1.1\nContent-Type: text/csv\n\na,b")
+                .await,
+            Some("text/csv".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body_file() {
+        let dir = std::env::temp_dir().join(format!("rq-body-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("user.json"), "{\"name\": \"rq\"}").unwrap();
+        let config = ClientConfig {
+            base_dir: dir.clone(),
+            ..Default::default()
+        };
+
+        let (url, rx) = serve("", Vec::new());
+        let file = parse(&format!(
+            "POST {}/ HTTP/1.1\n\n< user.json\n\n###\n\nPOST {}/ HTTP/1.1\n\n< missing.json\n",
+            url, url
+        ))
+        .unwrap();
+        execute(&file.requests[0], &config).await.unwrap();
+        let err = execute(&file.requests[1], &config).await.unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let request = rx.recv().unwrap();
+        assert!(
+            request.ends_with("\r\n\r\n{\"name\": \"rq\"}"),
+            "{}",
+            request
+        );
+        assert!(err.to_string().contains("missing.json"), "{}", err);
+    }
+
+    fn serve_gzip(body: &[u8]) -> (String, mpsc::Receiver<String>) {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(body).unwrap();
+        serve("Content-Encoding: gzip\r\n", encoder.finish().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_compressed_body_is_kept() {
+        let (url, _rx) = serve("Content-Encoding: gzip\r\n", b"not gzip".to_vec());
+        let file = parse(&format!("GET {}/ HTTP/1.1\n\n", url)).unwrap();
+        let response = execute(&file.requests[0], &ClientConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(response.body, "not gzip");
+        assert_eq!(response.headers["content-encoding"], "gzip");
+        assert!(response.compression.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_progress() {
+        let body = vec![b'a'; 64 * 1024];
+        let (url, _rx) = serve("", body.clone());
+        let file = parse(&format!("GET {}/ HTTP/1.1\n\n", url)).unwrap();
+        let mut updates = Vec::new();
+        let response = execute_with_progress(
+            &file.requests[0],
+            &ClientConfig::default(),
+            |received, total| updates.push((received, total)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.bytes.len(), body.len());
+        assert!(!response.elapsed.is_zero());
+        assert!(updates.windows(2).all(|w| w[0].0 < w[1].0), "{:?}", updates);
+        assert_eq!(
+            updates.last(),
+            Some(&(body.len() as u64, Some(body.len() as u64)))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_accept_encoding_is_sent_unmodified() {
+        let body = "compressible ".repeat(100);
+        let (url, rx) = serve_gzip(body.as_bytes());
+        let file = parse(&format!("GET {}/ HTTP/1.1\nAccept-Encoding: gzip\n\n", url)).unwrap();
+
+        let response = execute(&file.requests[0], &ClientConfig::default())
+            .await
+            .unwrap();
+
+        let request = rx.recv().unwrap().to_lowercase();
+        assert_eq!(request.matches("accept-encoding").count(), 1, "{}", request);
+        assert!(request.contains("accept-encoding: gzip\r\n"), "{}", request);
+
+        assert_eq!(response.body, body);
+        assert!(!response.headers.contains_key("content-encoding"));
+        assert_eq!(response.headers["content-length"], body.len().to_string());
+        let compression = response.compression.unwrap();
+        assert_eq!(compression.encoding, "gzip");
+        assert_eq!(compression.decompressed, body.len());
+        assert!(compression.ratio() > 1.0);
+    }
 
-    let headers: header::HeaderMap = (&req.headers).try_into()?;
+    #[tokio::test]
+    async fn test_invalid_user_agent() {
+        let file = parse("GET http://127.0.0.1:1/ HTTP/1.1\n\n").unwrap();
+        let config = ClientConfig {
+            default_user_agent: Some("rq\n1.0".to_string()),
+            ..Default::default()
+        };
+        let err = execute(&file.requests[0], &config).await.unwrap_err();
+        assert_eq!(err.to_string(), r#"invalid default user agent "rq\n1.0""#);
+    }
 
-    let body = req.body.clone();
-    let res = request.headers(headers).body(body).send().await?;
+    #[test]
+    fn test_sign_aws() {
+        let file = parse(
+            "# @auth aws-sigv4 eu-west-1 execute-api\nPOST https://api.dev/items HTTP/1.1\n\n{}\n",
+        )
+        .unwrap();
+        // Passed in, the credentials of the environment and profiles are not read
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".to_string(),
+            session_token: None,
+            expiration: None,
+        };
+        let now = UNIX_EPOCH + Duration::from_secs(1_440_938_160);
+        let mut headers = HeaderMap::new();
+        sign_aws(
+            &mut headers,
+            &file.requests[0],
+            Some(b"{}"),
+            &credentials,
+            now,
+        )
+        .unwrap();
 
-    let content = res.text().await?;
-    Ok(content)
+        let authorization = headers[header::AUTHORIZATION].to_str().unwrap();
+        assert!(
+            authorization.starts_with(
+                "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/eu-west-1/execute-api/aws4_request"
+            ),
+            "{}",
+            authorization
+        );
+        assert_eq!(headers["x-amz-date"], "20150830T123600Z");
+        // Hash of the `{}` body
+        assert_eq!(
+            headers["x-amz-content-sha256"],
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+    }
 }
@@ -0,0 +1,68 @@
+//! Classification of inline request bodies by their declared `Content-Type`
+
+use std::collections::HashMap;
+
+use super::form_pairs;
+
+/// How an inline body without `# @form`, `# @multipart` or `# @no-body-encode` is sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BodyKind {
+    /// Sent as written
+    Raw,
+    /// `application/x-www-form-urlencoded` pairs, percent-encoded when sent
+    Form(Vec<(String, String)>),
+    /// `application/json` and `+json` types, sent as written
+    Json,
+}
+
+/// Kind of `body` given the request `headers`. Without a `Content-Type` the body is raw
+pub(crate) fn classify(headers: &HashMap<String, String>, body: &str) -> BodyKind {
+    let content_type = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase()
+        });
+    match content_type.as_deref() {
+        Some("application/x-www-form-urlencoded") => BodyKind::Form(form_pairs(body)),
+        Some(mime) if mime == "application/json" || mime.ends_with("+json") => BodyKind::Json,
+        _ => BodyKind::Raw,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, BodyKind};
+    use std::collections::HashMap;
+
+    fn headers(content_type: &str) -> HashMap<String, String> {
+        HashMap::from([("content-type".to_string(), content_type.to_string())])
+    }
+
+    #[test]
+    fn test_classify() {
+        let body = "q=a%26b&name=rq cli";
+        assert_eq!(
+            classify(
+                &headers("Application/X-WWW-Form-Urlencoded; charset=utf-8"),
+                body
+            ),
+            BodyKind::Form(vec![
+                ("q".to_string(), "a&b".to_string()),
+                ("name".to_string(), "rq cli".to_string()),
+            ])
+        );
+        assert_eq!(classify(&headers("application/json"), body), BodyKind::Json);
+        assert_eq!(
+            classify(&headers("application/problem+json"), body),
+            BodyKind::Json
+        );
+        assert_eq!(classify(&headers("text/plain"), body), BodyKind::Raw);
+        assert_eq!(classify(&HashMap::new(), body), BodyKind::Raw);
+    }
+}
@@ -0,0 +1,31 @@
+use std::{future::Future, time::Duration};
+
+/// Spawns `future` on the tokio runtime
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(future);
+}
+
+/// Spawns `future` on the browser event loop
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    wasm_bindgen_futures::spawn_local(future);
+}
+
+/// Awaits `future`, returning `None` if it doesn't complete within `duration`
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    tokio::time::timeout(duration, future).await.ok()
+}
+
+/// Timers are not available on wasm, the future is awaited without a timeout
+#[cfg(target_arch = "wasm32")]
+pub async fn timeout<F: Future>(_duration: Duration, future: F) -> Option<F::Output> {
+    Some(future.await)
+}
@@ -0,0 +1,108 @@
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+
+/// Event of a `text/event-stream` response
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Value of the `event:` field, `None` for the default `message` type
+    pub event_type: Option<String>,
+    /// `data:` lines of the event joined with `\n`
+    pub data: String,
+    pub id: Option<String>,
+}
+
+/// Whether the response body is a stream of server-sent events
+pub fn is_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim_start().starts_with("text/event-stream"))
+}
+
+/// Splits an event stream in events, following the
+/// [HTML spec](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation).
+/// Comment lines, like `: keep-alive`, and events without data are dropped.
+/// Unlike the spec, an unterminated last event is kept since the stream is complete.
+pub fn parse_events(stream: &str) -> Vec<SseEvent> {
+    let mut events = Vec::new();
+    let mut event = SseEvent::default();
+    let mut data: Vec<&str> = Vec::new();
+    let mut dispatch = |event: &mut SseEvent, data: &mut Vec<&str>| {
+        if !data.is_empty() {
+            event.data = data.join("\n");
+            events.push(std::mem::take(event));
+        }
+        *event = SseEvent::default();
+        data.clear();
+    };
+
+    for line in stream.lines() {
+        if line.is_empty() {
+            dispatch(&mut event, &mut data);
+            continue;
+        }
+        if line.starts_with(':') {
+            continue;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "data" => data.push(value),
+            "event" => event.event_type = Some(value.to_string()),
+            "id" => event.id = Some(value.to_string()),
+            // `retry` and unknown fields only matter to reconnecting clients
+            _ => {}
+        }
+    }
+    dispatch(&mut event, &mut data);
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{HeaderMap, CONTENT_TYPE};
+
+    use super::{is_event_stream, parse_events, SseEvent};
+
+    #[test]
+    fn test_parse_events() {
+        let stream = ": keep-alive\r\n\r\n\
+                      event: update\r\n\
+                      id: 1\r\n\
+                      data: {\"a\":\r\n\
+                      data: 1}\r\n\r\n\
+                      :ping\n\n\
+                      data:no space\n\
+                      retry: 1000\n\n\
+                      event: empty\n\n\
+                      data: last";
+        assert_eq!(
+            parse_events(stream),
+            vec![
+                SseEvent {
+                    event_type: Some("update".to_string()),
+                    data: "{\"a\":\n1}".to_string(),
+                    id: Some("1".to_string()),
+                },
+                SseEvent {
+                    data: "no space".to_string(),
+                    ..Default::default()
+                },
+                SseEvent {
+                    data: "last".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_event_stream() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_event_stream(&headers));
+        headers.insert(
+            CONTENT_TYPE,
+            "text/event-stream; charset=utf-8".parse().unwrap(),
+        );
+        assert!(is_event_stream(&headers));
+    }
+}
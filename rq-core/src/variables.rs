@@ -0,0 +1,451 @@
+use serde_json::Value;
+
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use crate::{auth::apikey::inject_apikey, helpers, parser::HttpRequest, request::Response};
+
+/// Values captured from responses during a session, referenced as `{{name}}`
+pub type Variables = HashMap<String, String>;
+
+/// Replaces the `{{name}}` references of `input` with their value.
+/// Unknown variables and unclosed `{{` are left as is.
+pub fn substitute(input: &str, variables: &Variables) -> String {
+    let mut ret = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(mut start) = rest.find("{{") {
+        // The innermost braces delimit the name, e.g. in a JSON body `{{{id}}}`
+        while rest[start + 2..].starts_with('{') {
+            start += 1;
+        }
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + end].trim();
+        ret.push_str(&rest[..start]);
+        match variables.get(name) {
+            Some(value) => ret.push_str(value),
+            None => ret.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    ret.push_str(rest);
+    ret
+}
+
+/// Contents of the `{{...}}` placeholders of `input`, variables and `{{$helper}}` calls.
+/// Braces around other text, e.g. `{{"a": 1}}` in a JSON body, aren't placeholders
+pub(crate) fn references(input: &str) -> impl Iterator<Item = &str> {
+    input.split("{{").skip(1).filter_map(|rest| {
+        let (name, _) = rest.trim_start_matches('{').split_once("}}")?;
+        let name = name.trim();
+        let valid = |c: char| c.is_ascii_alphanumeric() || "_-.".contains(c);
+        (name.starts_with('$') || (!name.is_empty() && name.chars().all(valid))).then_some(name)
+    })
+}
+
+/// Where the value of a placeholder came from, see [`HttpRequest::trace_variables`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableSource {
+    /// Captured from a response, with `x` or `# @capture-header`
+    Capture,
+    /// Environment variable or `.env` file, see [`resolve`]
+    Env,
+    /// Evaluated by a `{{$helper}}`, see [`crate::helpers`]
+    Helper,
+    Unresolved,
+}
+
+impl Display for VariableSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Capture => "capture",
+            Self::Env => "env",
+            Self::Helper => "helper",
+            Self::Unresolved => "unresolved",
+        })
+    }
+}
+
+/// Value a placeholder resolved to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolution {
+    /// Content of the placeholder, e.g. `token` for `{{token}}`
+    pub placeholder: String,
+    /// Error of an unresolved placeholder or a failed helper
+    pub value: Result<String, String>,
+    pub source: VariableSource,
+}
+
+impl Resolution {
+    /// Value to display, masked when the placeholder looks like a secret
+    pub fn display_value(&self) -> String {
+        const SECRETS: [&str; 6] = ["token", "secret", "password", "key", "auth", "cookie"];
+        match &self.value {
+            Ok(value) => {
+                let name = self.placeholder.to_lowercase();
+                if SECRETS.iter().any(|secret| name.contains(secret)) {
+                    "*".repeat(value.chars().count().min(8))
+                } else {
+                    value.clone()
+                }
+            }
+            Err(e) => format!("<{}>", e),
+        }
+    }
+}
+
+impl HttpRequest {
+    /// Placeholders of the url, query, headers, body and credentials, in order
+    fn placeholders(&self) -> impl Iterator<Item = &str> {
+        let mut headers: Vec<_> = self.headers.iter().collect();
+        headers.sort();
+        references(&self.url)
+            .chain(self.query.iter().flat_map(|(_, v)| references(v)))
+            .chain(headers.into_iter().flat_map(|(_, v)| references(v)))
+            .chain(references(&self.body))
+            .chain(self.api_key.iter().flat_map(|key| references(&key.value)))
+            .chain(self.oauth2.iter().flat_map(|oauth| {
+                references(&oauth.token_url)
+                    .chain(references(&oauth.client_id))
+                    .chain(references(&oauth.client_secret))
+            }))
+    }
+
+    /// Names of the variables still referenced by the url, query, headers, body or credentials,
+    /// sorted and without duplicates. `{{$helper}}` calls are evaluated when sending
+    pub fn unresolved_variables(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .placeholders()
+            .filter(|name| !name.starts_with('$'))
+            .map(str::to_string)
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// What each placeholder of the request resolves to, in order of appearance and without
+    /// duplicates. Captured `variables` take precedence over `env`. Helper files are relative
+    /// to `base_dir`
+    pub fn trace_variables(
+        &self,
+        variables: &Variables,
+        env: &Variables,
+        base_dir: &Path,
+    ) -> Vec<Resolution> {
+        let mut trace: Vec<Resolution> = Vec::new();
+        for placeholder in self.placeholders() {
+            if trace.iter().any(|r| r.placeholder == placeholder) {
+                continue;
+            }
+            let (value, source) = if placeholder.starts_with('$') {
+                match helpers::call(placeholder, base_dir) {
+                    Ok(Some(value)) => (Ok(value), VariableSource::Helper),
+                    Ok(None) => (
+                        Err("unknown helper".to_string()),
+                        VariableSource::Unresolved,
+                    ),
+                    Err(e) => (Err(e), VariableSource::Helper),
+                }
+            } else {
+                match (variables.get(placeholder), env.get(placeholder)) {
+                    (Some(value), _) => (Ok(value.clone()), VariableSource::Capture),
+                    (None, Some(value)) => (Ok(value.clone()), VariableSource::Env),
+                    (None, None) => (Err("unresolved".to_string()), VariableSource::Unresolved),
+                }
+            };
+            trace.push(Resolution {
+                placeholder: placeholder.to_string(),
+                value,
+                source,
+            });
+        }
+        trace
+    }
+
+    /// Returns a copy of the request with the variables of the url, query, headers and body replaced,
+    /// and the API key injected
+    pub fn with_variables(&self, variables: &Variables) -> HttpRequest {
+        let mut ret = self.clone();
+        ret.url = substitute(&self.url, variables);
+        ret.query = self
+            .query
+            .iter()
+            .map(|(k, v)| (k.clone(), substitute(v, variables)))
+            .collect();
+        ret.headers = self
+            .headers
+            .iter()
+            .map(|(k, v)| (k.clone(), substitute(v, variables)))
+            .collect();
+        ret.body = substitute(&self.body, variables);
+        if let Some(oauth) = ret.oauth2.as_mut() {
+            oauth.token_url = substitute(&oauth.token_url, variables);
+            oauth.client_id = substitute(&oauth.client_id, variables);
+            oauth.client_secret = substitute(&oauth.client_secret, variables);
+        }
+        if let Some(api_key) = ret.api_key.take() {
+            let value = substitute(&api_key.value, variables);
+            inject_apikey(&mut ret, api_key.via, &api_key.name, &value);
+        }
+        ret
+    }
+}
+
+/// Replaces the `{{NAME}}` references of the url, query, headers, body and credentials
+/// with the values of `env`, e.g. environment variables.
+/// Fails with the sorted names of the variables `env` doesn't define
+pub fn resolve(req: &HttpRequest, env: &Variables) -> Result<HttpRequest, Vec<String>> {
+    let ret = req.with_variables(env);
+    let unresolved = ret.unresolved_variables();
+    if unresolved.is_empty() {
+        Ok(ret)
+    } else {
+        Err(unresolved)
+    }
+}
+
+/// Stores the response headers captured by `# @capture-header`.
+/// Returns the names of the headers missing in the response.
+pub fn capture_headers(
+    req: &HttpRequest,
+    response: &Response,
+    variables: &mut Variables,
+) -> Vec<String> {
+    let mut missing = Vec::new();
+    for (name, header) in &req.header_captures {
+        // `HeaderMap` lookups are case insensitive
+        match response.headers.get(header).and_then(|v| v.to_str().ok()) {
+            Some(value) => {
+                variables.insert(name.clone(), value.to_string());
+            }
+            None => missing.push(header.clone()),
+        }
+    }
+    missing
+}
+
+/// Extracts a value from the response. `expression` is either a JSON path
+/// starting with `$`, e.g. `$.data.items[0].id`, or a header name.
+pub fn extract(response: &Response, expression: &str) -> Option<String> {
+    let expression = expression.trim();
+    if !expression.starts_with('$') {
+        return response
+            .headers
+            .get(expression)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+    }
+
+    let body: Value = serde_json::from_str(&response.body).ok()?;
+    match json_path(&body, expression)? {
+        Value::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// Resolves dot and index segments: `$.a.b[0]`
+fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.trim_start_matches('$')
+        .replace('[', ".[")
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |value, segment| {
+            match segment.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                Some(index) => value.get(index.parse::<usize>().ok()?),
+                None => value.get(segment),
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{header::HeaderMap, StatusCode, Version};
+
+    use super::{capture_headers, extract, resolve, substitute, VariableSource, Variables};
+    use crate::{parser::parse, request::Response};
+    use std::path::Path;
+
+    fn response(headers: &[(&'static str, &'static str)]) -> Response {
+        response_with_body(headers, "")
+    }
+
+    fn response_with_body(headers: &[(&'static str, &'static str)], body: &str) -> Response {
+        let mut map = HeaderMap::new();
+        for (k, v) in headers {
+            map.insert(*k, v.parse().unwrap());
+        }
+        Response {
+            version: Version::HTTP_11,
+            status: StatusCode::OK,
+            headers: map,
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_substitute() {
+        let variables = Variables::from([("id".to_string(), "42".to_string())]);
+        assert_eq!(substitute("/users/{{id}}", &variables), "/users/42");
+        assert_eq!(substitute("/users/{{ id }}/", &variables), "/users/42/");
+        assert_eq!(substitute("{{other}}-{{id}}", &variables), "{{other}}-42");
+        assert_eq!(substitute("{{id", &variables), "{{id");
+        assert_eq!(
+            substitute(r#"{"a": {{{id}}}, "b": {"c": {{id}}}}"#, &variables),
+            r#"{"a": {42}, "b": {"c": 42}}"#
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let file = parse(
+            "# @header-template auth\nAuthorization: Bearer {{TOKEN}}\n\n# @use-header-template auth\nPOST {{BASE_URL}}/users/{{USER_ID}} HTTP/1.1\nX-Trace: {{USER_ID}}-{{TRACE}}\n\n{\"id\": {{USER_ID}}}\n",
+        )
+        .unwrap();
+        let req = &file.requests[0];
+        let mut env = Variables::from([
+            ("BASE_URL".to_string(), "https://api.dev".to_string()),
+            ("USER_ID".to_string(), "42".to_string()),
+            ("TOKEN".to_string(), "s3cr3t".to_string()),
+        ]);
+
+        assert_eq!(resolve(req, &env).unwrap_err(), ["TRACE"]);
+        assert_eq!(
+            resolve(req, &Variables::new()).unwrap_err(),
+            ["BASE_URL", "TOKEN", "TRACE", "USER_ID"]
+        );
+
+        env.insert("TRACE".to_string(), "t1".to_string());
+        let resolved = resolve(req, &env).unwrap();
+        assert_eq!(resolved.url, "https://api.dev/users/42");
+        assert_eq!(resolved.headers["Authorization"], "Bearer s3cr3t");
+        assert_eq!(resolved.headers["X-Trace"], "42-t1");
+        assert_eq!(resolved.body.trim(), "{\"id\": 42}");
+    }
+
+    #[test]
+    fn test_unresolved_variables() {
+        let file = parse(
+            "GET https://{{host}}/users/{{id}} HTTP/1.1\n?\npage = {{page}}\nAuthorization: Bearer {{ token }}\n\n{\"id\": \"{{id}}\", \"key\": \"{{$base64 id}}\"}\n",
+        )
+        .unwrap();
+        let req = &file.requests[0];
+        assert_eq!(req.unresolved_variables(), ["host", "id", "page", "token"]);
+
+        let variables = Variables::from([
+            ("host".to_string(), "api.dev".to_string()),
+            ("id".to_string(), "42".to_string()),
+        ]);
+        assert_eq!(
+            req.with_variables(&variables).unresolved_variables(),
+            ["page", "token"]
+        );
+    }
+
+    #[test]
+    fn test_trace_variables() {
+        let file = parse(
+            "GET https://{{host}}/users/{{id}} HTTP/1.1\nAuthorization: Bearer {{token}}\n\n{\"id\": \"{{id}}\", \"name\": \"{{$base64 rq}}\", \"key\": \"{{$base64File missing.pem}}\"}\n",
+        )
+        .unwrap();
+        let variables = Variables::from([
+            ("host".to_string(), "api.dev".to_string()),
+            ("token".to_string(), "s3cr3t".to_string()),
+        ]);
+        let env = Variables::from([
+            ("host".to_string(), "env.dev".to_string()),
+            ("id".to_string(), "7".to_string()),
+        ]);
+        let trace = file.requests[0].trace_variables(&variables, &env, Path::new("/nonexistent"));
+
+        let rows: Vec<_> = trace
+            .iter()
+            .map(|r| (r.placeholder.as_str(), r.source, r.value.is_ok()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("host", VariableSource::Capture, true),
+                ("id", VariableSource::Env, true),
+                ("token", VariableSource::Capture, true),
+                ("$base64 rq", VariableSource::Helper, true),
+                ("$base64File missing.pem", VariableSource::Helper, false),
+            ]
+        );
+        assert_eq!(trace[0].display_value(), "api.dev");
+        assert_eq!(trace[1].display_value(), "7");
+        let trace = file.requests[0].trace_variables(&variables, &Variables::new(), Path::new("."));
+        assert_eq!(trace[1].source, VariableSource::Unresolved);
+        assert_eq!(trace[1].display_value(), "<unresolved>");
+        assert_eq!(trace[2].display_value(), "******");
+        assert_eq!(trace[3].display_value(), "cnE=");
+    }
+
+    #[test]
+    fn test_capture_header() {
+        let file = parse(
+            "# @capture-header csrf X-CSRF-Token
+GET https://api.dev/form HTTP/1.1
+
+###
+
+POST https://api.dev/form HTTP/1.1
+X-CSRF-Token: {{csrf}}
+
+",
+        )
+        .unwrap();
+        let mut variables = Variables::new();
+
+        let missing = capture_headers(
+            &file.requests[0],
+            &response(&[("x-csrf-token", "abc123")]),
+            &mut variables,
+        );
+        assert!(missing.is_empty());
+
+        let req = file.requests[1].with_variables(&variables);
+        assert_eq!(req.headers["X-CSRF-Token"], "abc123");
+    }
+
+    #[test]
+    fn test_capture_missing_header() {
+        let file = parse("# @capture-header csrf X-CSRF-Token\nGET /form HTTP/1.1\n\n").unwrap();
+        let mut variables = Variables::new();
+
+        let missing = capture_headers(&file.requests[0], &response(&[]), &mut variables);
+        assert_eq!(missing, vec!["X-CSRF-Token".to_string()]);
+        assert!(variables.is_empty());
+    }
+
+    #[test]
+    fn test_extract() {
+        let response = response_with_body(
+            &[("x-request-id", "abc")],
+            r#"{ "data": { "items": [{ "id": 7, "name": "rq" }] } }"#,
+        );
+        assert_eq!(extract(&response, "X-Request-Id").as_deref(), Some("abc"));
+        assert_eq!(
+            extract(&response, "$.data.items[0].id").as_deref(),
+            Some("7")
+        );
+        assert_eq!(
+            extract(&response, "$.data.items[0].name").as_deref(),
+            Some("rq")
+        );
+        assert_eq!(extract(&response, "$.data.items[1]"), None);
+        assert_eq!(extract(&response, "x-missing"), None);
+    }
+
+    #[test]
+    fn test_extracted_value_is_substituted() {
+        let response = response_with_body(&[], r#"{ "token": "secret" }"#);
+        let mut variables = Variables::new();
+        variables.insert("token".to_string(), extract(&response, "$.token").unwrap());
+
+        let file = parse("GET /me HTTP/1.1\nAuthorization: Bearer {{token}}\n\n").unwrap();
+        let req = file.requests[0].with_variables(&variables);
+        assert_eq!(req.headers["Authorization"], "Bearer secret");
+    }
+}